@@ -21,6 +21,8 @@ pub struct Name {
 #[derive(Deserialize, OpenAISchema, Debug)]
 /// The niceness score.
 pub struct NicenessScoreContainer {
+    // the model occasionally sends the score as a string
+    #[serde(deserialize_with = "kind_openai::de::lenient_repr")]
     pub niceness_score: NicenessScore,
     pub category: Category,
 }
@@ -65,7 +67,8 @@ async fn main() {
         ])
         .temperature(0.1)
         .logit_bias(logit_bias)
-        .structured::<Name>();
+        .structured::<Name>()
+        .expect("Invalid request");
 
     let name = client
         .req(&chat_completion)
@@ -85,7 +88,8 @@ async fn main() {
             system_message!("Rate the niceness score of the provided message"),
             user_message!("{niceness_score_message}"),
         ])
-        .structured::<NicenessScoreContainer>();
+        .structured::<NicenessScoreContainer>()
+        .expect("Invalid request");
 
     let niceness_score = client
         .req(&niceness_chat_completion)
@@ -105,7 +109,8 @@ async fn main() {
             system_message!("Rate the niceness score of the provided message"),
             user_message!("{niceness_score_message}"),
         ])
-        .structured::<NicenessScoreContainer>();
+        .structured::<NicenessScoreContainer>()
+        .expect("Invalid request");
 
     let niceness_score = client
         .req(&niceness_chat_completion)
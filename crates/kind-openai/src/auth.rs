@@ -1,3 +1,7 @@
+use std::{path::PathBuf, sync::Arc, time::SystemTime};
+
+use tokio::sync::RwLock;
+
 /// Any type that can provide a bearer auth token.
 pub trait AuthTokenProvider: Clone {
     async fn resolve(&self) -> Option<String>;
@@ -16,3 +20,63 @@ impl AuthTokenProvider for EnvironmentAuthTokenProvider {
         std::env::var(Self::ENV_VAR).ok()
     }
 }
+
+/// Auth token provider that reads the auth token from a file, such as a mounted Kubernetes secret.
+///
+/// The file is only re-read when its modification time changes, so rotated tokens are picked up
+/// without a restart. If the file is missing or unreadable, no token is provided. Clones share the
+/// same cache. The file is read with `tokio::fs`, so resolving requires a Tokio runtime.
+#[derive(Clone)]
+pub struct FileAuthTokenProvider {
+    path: PathBuf,
+    cache: Arc<RwLock<Option<CachedToken>>>,
+}
+
+struct CachedToken {
+    modified: SystemTime,
+    token: String,
+}
+
+impl FileAuthTokenProvider {
+    /// Creates a provider that reads the token from the file at the provided path. Surrounding
+    /// whitespace (such as a trailing newline) is trimmed from the token.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache: Arc::default(),
+        }
+    }
+}
+
+impl AuthTokenProvider for FileAuthTokenProvider {
+    async fn resolve(&self) -> Option<String> {
+        // the lock is never held across reading the file, so that a slow filesystem only holds up the
+        // caller that's reading it.
+        let Ok(modified) = tokio::fs::metadata(&self.path)
+            .await
+            .and_then(|meta| meta.modified())
+        else {
+            *self.cache.write().await = None;
+            return None;
+        };
+
+        if let Some(cached) = self
+            .cache
+            .read()
+            .await
+            .as_ref()
+            .filter(|cached| cached.modified == modified)
+        {
+            return Some(cached.token.clone());
+        }
+
+        let token = tokio::fs::read_to_string(&self.path)
+            .await
+            .ok()
+            .map(|token| token.trim().to_owned())
+            .filter(|token| !token.is_empty());
+        *self.cache.write().await = token.clone().map(|token| CachedToken { modified, token });
+
+        token
+    }
+}
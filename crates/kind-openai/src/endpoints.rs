@@ -3,11 +3,30 @@ use serde::{Deserialize, Serialize};
 
 use crate::{auth, error::OpenAIAPIError, OpenAI, OpenAIResult};
 
+pub mod batches;
 pub mod chat;
 pub mod chat_reasoning;
+pub mod completions;
 pub mod embeddings;
+pub mod files;
+pub mod fine_tuning;
+pub mod moderations;
+mod pagination;
+mod sampling;
+mod stream;
 
-const API_BASE_URL: &str = "https://api.openai.com/v1";
+pub(crate) use pagination::paginate;
+pub use pagination::Page;
+pub use sampling::SamplingParams;
+pub(crate) use stream::send_streaming_request;
+pub use stream::{OpenAIStreamingRequestProvider, StreamLimitPolicy};
+
+#[cfg(not(feature = "middleware"))]
+pub(crate) type HttpRequestBuilder = reqwest::RequestBuilder;
+#[cfg(feature = "middleware")]
+pub(crate) type HttpRequestBuilder = reqwest_middleware::RequestBuilder;
+
+pub(crate) const API_BASE_URL: &str = "https://api.openai.com/v1";
 
 // this enum and the struct below it are hacks to deal with openai's weird response format
 // where they will return either a single error field or the success payload.
@@ -27,7 +46,7 @@ impl<T> From<GenericOpenAIResponse<T>> for OpenAIResult<T> {
     fn from(value: GenericOpenAIResponse<T>) -> Self {
         match value {
             GenericOpenAIResponse::Success(success) => Ok(success),
-            GenericOpenAIResponse::Error(error) => Err(crate::OpenAIError::API(error.error)),
+            GenericOpenAIResponse::Error(error) => Err(error.error.into()),
         }
     }
 }
@@ -40,34 +59,218 @@ where
     Auth: auth::AuthTokenProvider,
     R: OpenAIRequestProvider,
 {
+    // take the response text and deserialize by hand so we can log response
+    // bodies that don't conform to the same structure
+    let response = request_builder(openai, request, request)
+        .await?
+        .send()
+        .await?;
+    openai.record_rate_limits(response.headers());
+    let status = response.status();
+    let response_text = response.text().await?;
+
+    deserialize_response(status, response_text)
+}
+
+pub(super) async fn send_request_raw<Auth, R>(
+    openai: &OpenAI<Auth>,
+    request: &R,
+) -> OpenAIResult<RawResponse<R::Response>>
+where
+    Auth: auth::AuthTokenProvider,
+    R: OpenAIRequestProvider,
+{
+    let response = request_builder(openai, request, request)
+        .await?
+        .send()
+        .await?;
+    openai.record_rate_limits(response.headers());
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().await?;
+
+    Ok(RawResponse {
+        response: deserialize_response(status, body.clone())?,
+        status,
+        headers,
+        body,
+    })
+}
+
+/// A parsed response alongside the HTTP details it arrived with, as given by `OpenAI::req_raw`.
+#[derive(Debug, Clone)]
+pub struct RawResponse<T> {
+    response: T,
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: String,
+}
+
+impl<T> RawResponse<T> {
+    pub fn response(&self) -> &T {
+        &self.response
+    }
+
+    /// Consumes the raw response and gives the parsed response.
+    pub fn into_response(self) -> T {
+        self.response
+    }
+
+    pub fn status(&self) -> reqwest::StatusCode {
+        self.status
+    }
+
+    /// The response headers, which include OpenAI's rate limit headers such as `x-ratelimit-remaining-requests`.
+    pub fn headers(&self) -> &reqwest::header::HeaderMap {
+        &self.headers
+    }
+
+    /// The response body, exactly as it was received.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+/// Builds the authenticated request for the provided request type, with the provided body.
+pub(super) async fn request_builder<Auth, R>(
+    openai: &OpenAI<Auth>,
+    request: &R,
+    body: &impl Serialize,
+) -> OpenAIResult<HttpRequestBuilder>
+where
+    Auth: auth::AuthTokenProvider,
+    R: OpenAIRequestProvider,
+{
+    request.validate()?;
+
     let bearer_token = openai
         .auth
         .resolve()
         .await
         .ok_or(crate::error::OpenAIError::MissingAuthToken)?;
 
-    // take the response text and deserialize by hand so we can log response
-    // bodies that don't conform to the same structure
-    let response_text = openai
+    let builder = openai
         .client
         .request(
             R::METHOD,
-            format!("{API_BASE_URL}{}", R::path_with_leading_slash()),
+            format!("{}{}", openai.base_url, request.path_with_leading_slash()),
         )
-        .header("Authorization", format!("Bearer {bearer_token}"))
-        // TODO: support a way to omit the body during a get request if the time comes
-        .json(request)
-        .send()
-        .await?
-        .text()
-        .await?;
+        .headers(openai.extra_headers.clone())
+        .header("Authorization", format!("Bearer {bearer_token}"));
+
+    // appended after any query the path already carries
+    let query_params = request.query_params();
+    let builder = if query_params.is_empty() {
+        builder
+    } else {
+        builder.query(&query_params)
+    };
+
+    if let Some(form) = request.multipart_form() {
+        return Ok(builder.multipart(form));
+    }
+
+    // get and delete requests carry everything they need in the path
+    if R::METHOD == Method::GET || R::METHOD == Method::DELETE {
+        Ok(builder)
+    } else if let Some(serde_json::Value::Object(extra)) = request.extra_body() {
+        Ok(builder.json(&WithExtraBody { body, extra }))
+    } else {
+        Ok(builder.json(body))
+    }
+}
+
+// serializes the body with the extra fields merged in, where any field the body already sets wins.
+struct WithExtraBody<'b, B> {
+    body: &'b B,
+    extra: &'b serde_json::Map<String, serde_json::Value>,
+}
 
-    match serde_json::from_str::<GenericOpenAIResponse<R::Response>>(&response_text) {
+impl<B> Serialize for WithExtraBody<'_, B>
+where
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut body = serde_json::to_value(self.body).map_err(serde::ser::Error::custom)?;
+        if let serde_json::Value::Object(fields) = &mut body {
+            for (key, value) in self.extra {
+                // unset typed fields are serialized as nulls, which shouldn't shadow the extra field
+                if fields.get(key).is_none_or(serde_json::Value::is_null) {
+                    fields.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        body.serialize(serializer)
+    }
+}
+
+pub(super) fn deserialize_response<T>(
+    status: reqwest::StatusCode,
+    response_text: String,
+) -> OpenAIResult<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(unauthorized_error(response_text));
+    }
+
+    match serde_json::from_str::<GenericOpenAIResponse<T>>(&response_text) {
         Ok(response) => response.into(),
         Err(err) => Err(crate::error::OpenAIError::Serde(response_text, err)),
     }
 }
 
+// a 401 means the auth token was rejected, whatever type or code the body gives (if it's even json, which it
+// might not be when a proxy rejected the request), so only the message is kept.
+pub(super) fn unauthorized_error(response_text: String) -> crate::error::OpenAIError {
+    #[derive(Deserialize)]
+    struct Body {
+        error: Message,
+    }
+
+    #[derive(Deserialize)]
+    struct Message {
+        message: String,
+    }
+
+    let message = serde_json::from_str::<Body>(&response_text)
+        .map_or(response_text, |body| body.error.message);
+    crate::error::OpenAIError::Unauthorized(message)
+}
+
+// the usual rule of thumb for english text is ~4 characters per token. this is only ever used for
+// best-effort limits, never for anything that needs to be exact.
+pub(crate) fn estimate_tokens(input: &str) -> usize {
+    input.len().div_ceil(4)
+}
+
+// lists the available models, which is only used by `OpenAI::validate_auth` as a cheap, unbilled request
+// that requires a valid auth token.
+#[derive(Serialize)]
+pub(crate) struct ListModels;
+
+#[derive(Deserialize)]
+pub(crate) struct ModelList {
+    #[allow(dead_code)]
+    data: Vec<serde::de::IgnoredAny>,
+}
+
+impl OpenAIRequestProvider for ListModels {
+    type Response = ModelList;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/models".to_string()
+    }
+}
+
+impl private::Sealed for ListModels {}
+
 mod private {
     pub trait Sealed {}
 }
@@ -77,5 +280,25 @@ pub trait OpenAIRequestProvider: Serialize + private::Sealed {
     type Response: for<'de> Deserialize<'de>;
     const METHOD: Method;
 
-    fn path_with_leading_slash() -> String;
+    fn path_with_leading_slash(&self) -> String;
+
+    /// Checks the request for mistakes that can be caught locally, before a round trip to OpenAI.
+    fn validate(&self) -> OpenAIResult<()> {
+        Ok(())
+    }
+
+    /// The multipart form to send in place of the JSON body, for endpoints that accept file uploads.
+    fn multipart_form(&self) -> Option<reqwest::multipart::Form> {
+        None
+    }
+
+    /// Extra fields to merge into the JSON body, for parameters that don't have typed support yet.
+    fn extra_body(&self) -> Option<&serde_json::Value> {
+        None
+    }
+
+    /// Query parameters to append to the URL, in order, for list endpoints and other GET requests.
+    fn query_params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 }
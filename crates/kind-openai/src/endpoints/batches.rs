@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use bon::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::OpenAIRequestProvider;
+
+/// The endpoint that every request in a batch is sent to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchEndpoint {
+    #[serde(rename = "/v1/chat/completions")]
+    ChatCompletions,
+    #[serde(rename = "/v1/embeddings")]
+    Embeddings,
+    #[serde(rename = "/v1/completions")]
+    Completions,
+}
+
+/// The time frame within which the batch should be processed. Currently only `24h` is supported.
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub enum BatchCompletionWindow {
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHours,
+}
+
+/// A request to create a batch from an uploaded file of requests. The file must be uploaded with
+/// the `batch` purpose.
+///
+/// Construct with `CreateBatch::endpoint`
+#[derive(Serialize, Debug, Clone, Builder)]
+#[builder(start_fn = endpoint)]
+pub struct CreateBatch<'a> {
+    #[builder(start_fn)]
+    endpoint: BatchEndpoint,
+    input_file_id: &'a str,
+    #[builder(default)]
+    completion_window: BatchCompletionWindow,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<HashMap<String, String>>,
+}
+
+impl OpenAIRequestProvider for CreateBatch<'_> {
+    type Response = Batch;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/batches".to_string()
+    }
+}
+
+impl super::private::Sealed for CreateBatch<'_> {}
+
+/// A request to retrieve the current state of a batch.
+#[derive(Serialize, Debug, Clone)]
+pub struct RetrieveBatch<'a> {
+    #[serde(skip)]
+    id: &'a str,
+}
+
+impl<'a> RetrieveBatch<'a> {
+    /// Creates a request to retrieve the batch with the provided ID.
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
+    }
+}
+
+impl OpenAIRequestProvider for RetrieveBatch<'_> {
+    type Response = Batch;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        format!("/batches/{}", self.id)
+    }
+}
+
+impl super::private::Sealed for RetrieveBatch<'_> {}
+
+/// A request to cancel an in-progress batch. The batch will be `cancelling` for up to 10 minutes
+/// before becoming `cancelled`.
+#[derive(Serialize, Debug, Clone)]
+pub struct CancelBatch<'a> {
+    #[serde(skip)]
+    id: &'a str,
+}
+
+impl<'a> CancelBatch<'a> {
+    /// Creates a request to cancel the batch with the provided ID.
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
+    }
+}
+
+impl OpenAIRequestProvider for CancelBatch<'_> {
+    type Response = Batch;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        format!("/batches/{}/cancel", self.id)
+    }
+}
+
+impl super::private::Sealed for CancelBatch<'_> {}
+
+/// A batch of requests.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Batch {
+    id: String,
+    endpoint: String,
+    input_file_id: String,
+    status: BatchStatus,
+    output_file_id: Option<String>,
+    error_file_id: Option<String>,
+    created_at: i64,
+    completed_at: Option<i64>,
+    request_counts: Option<BatchRequestCounts>,
+    metadata: Option<HashMap<String, String>>,
+}
+
+impl Batch {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The endpoint that the requests in the batch are sent to, for example `/v1/chat/completions`.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn input_file_id(&self) -> &str {
+        &self.input_file_id
+    }
+
+    pub fn status(&self) -> BatchStatus {
+        self.status
+    }
+
+    /// The ID of the file containing the successful outputs, only present once the batch has completed.
+    pub fn output_file_id(&self) -> Option<&str> {
+        self.output_file_id.as_deref()
+    }
+
+    /// The ID of the file containing the outputs of requests that errored.
+    pub fn error_file_id(&self) -> Option<&str> {
+        self.error_file_id.as_deref()
+    }
+
+    /// The unix timestamp (in seconds) of when the batch was created.
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// The unix timestamp (in seconds) of when the batch completed, if it has.
+    pub fn completed_at(&self) -> Option<i64> {
+        self.completed_at
+    }
+
+    pub fn request_counts(&self) -> Option<BatchRequestCounts> {
+        self.request_counts
+    }
+
+    pub fn metadata(&self) -> Option<&HashMap<String, String>> {
+        self.metadata.as_ref()
+    }
+}
+
+/// The status of a batch.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+    /// A status that this crate doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+/// How many of the requests in a batch have been processed.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct BatchRequestCounts {
+    pub total: u32,
+    pub completed: u32,
+    pub failed: u32,
+}
@@ -1,16 +1,32 @@
+mod audio;
+mod content;
+mod repair;
 mod standard;
+mod stored;
+mod stream;
 mod structured;
+#[cfg(feature = "tokenizer")]
+mod tokenizer;
+mod tools;
 
+pub use audio::*;
+pub use content::*;
+pub use repair::*;
 pub use standard::*;
+pub use stored::*;
+pub use stream::*;
 pub use structured::*;
+#[cfg(feature = "tokenizer")]
+pub use tokenizer::*;
+pub use tools::*;
 
 use std::borrow::Cow;
 
-use bon::{builder, Builder};
+use bon::Builder;
 use serde::{Deserialize, Serialize};
 
 /// The model that can be used for either standard or structured chat completions.
-#[derive(Serialize, Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 #[allow(non_camel_case_types)]
 pub enum Model {
     #[serde(rename = "gpt-4o-2024-11-20")]
@@ -21,12 +37,28 @@ pub enum Model {
     Gpt4o,
     #[serde(rename = "gpt-4o-mini")]
     Gpt4oMini,
+    /// Can listen to and speak audio, see `ChatCompletion::modalities`.
+    #[serde(rename = "gpt-4o-audio-preview")]
+    Gpt4oAudioPreview,
+}
+
+impl Model {
+    /// The maximum number of tokens, across both the prompt and the completion, that the model accepts.
+    pub fn context_window(&self) -> u32 {
+        match self {
+            Model::Gpt4o_2024_11_20
+            | Model::Gpt4o_2024_08_06
+            | Model::Gpt4o
+            | Model::Gpt4oMini
+            | Model::Gpt4oAudioPreview => 128_000,
+        }
+    }
 }
 
 pub use standard::{ChatCompletion, ChatCompletionBuilder};
 pub use structured::StructuredChatCompletion;
 
-use crate::{OpenAIError, OpenAIResult};
+use crate::{OpenAIError, OpenAIResult, Usage};
 
 /// The role of the message used for the chat completion.
 #[derive(Serialize, Debug, Deserialize, Clone, Copy)]
@@ -40,19 +72,79 @@ pub enum Role {
     User,
     /// The assistant message, i.e. the model's response.
     Assistant,
+    /// The developer message, which newer models treat as the successor to the system message.
+    Developer,
+    /// The result of a tool call made by the assistant, which must carry the `tool_call_id` of the call.
+    Tool,
 }
 
 /// A chat completion message. You can pre-populate the request with user and
 /// assistant messages (alongside the system message) to provide context for the
 /// completion.
-#[derive(Serialize, Debug, Builder)]
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
 #[builder(start_fn = role)]
 pub struct Message<'a> {
     #[builder(start_fn)]
     role: Role,
-    content: Cow<'a, str>,
-    refusal: Option<&'a str>,
+    /// The content of the message, which may only be left out of assistant messages that make tool calls,
+    /// refuse, or speak audio. It's still sent as `null` in that case, as OpenAI expects.
+    content: Option<MessageContent<'a>>,
+    /// The refusal of an assistant message, kept apart from the content so that a replayed conversation
+    /// doesn't present it to the model as an ordinary answer.
+    refusal: Option<Cow<'a, str>>,
     name: Option<Cow<'a, str>>,
+    /// The ID of the tool call that a tool message is the result of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<Cow<'a, str>>,
+    /// The tool calls that an assistant message made, for replaying a conversation that used tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall<'a>>>,
+    /// The audio that an assistant message spoke, for replaying a conversation that used `Modality::Audio`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio: Option<AudioReference<'a>>,
+}
+
+impl Message<'_> {
+    /// Creates a user message from a fixed template, where every `{name}` placeholder is replaced by
+    /// the matching variable wrapped in `<name>` tags. Angle brackets inside of the variables are
+    /// escaped so that untrusted input can't close its own tag and masquerade as instructions, and
+    /// placeholders inside of variables are never expanded. Placeholders without a matching variable
+    /// are left as is.
+    ///
+    /// ```rust,ignore
+    /// Message::user_template("Summarize the review: {review}", &[("review", untrusted_review)]);
+    /// ```
+    pub fn user_template(template: &str, vars: &[(&str, &str)]) -> Message<'static> {
+        let mut content = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            content.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let var = rest.find('}').and_then(|end| {
+                let name = &rest[1..end];
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| (end, name, value))
+            });
+
+            match var {
+                Some((end, name, value)) => {
+                    let value = value.replace('<', "&lt;").replace('>', "&gt;");
+                    content.push_str(&format!("<{name}>\n{value}\n</{name}>"));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    content.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        content.push_str(rest);
+
+        Message::role(Role::User).content(content.into()).build()
+    }
 }
 
 #[macro_export]
@@ -66,6 +158,17 @@ macro_rules! system_message {
     };
 }
 
+#[macro_export]
+macro_rules! developer_message {
+    ($($arg:tt)*) => {
+        ::kind_openai::endpoints::chat::Message::role(
+            ::kind_openai::endpoints::chat::Role::Developer
+        )
+        .content(format!($($arg)*).into())
+        .build();
+    };
+}
+
 #[macro_export]
 macro_rules! user_message {
     ($($arg:tt)*) => {
@@ -88,11 +191,64 @@ macro_rules! assistant_message {
     };
 }
 
+/// A call to a tool made by the assistant.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolCall<'a> {
+    Function {
+        id: Cow<'a, str>,
+        function: FunctionCall<'a>,
+    },
+}
+
+impl<'a> ToolCall<'a> {
+    /// Creates a call to the named function, where the arguments are the JSON encoded string the model produced.
+    pub fn function(
+        id: impl Into<Cow<'a, str>>,
+        name: impl Into<Cow<'a, str>>,
+        arguments: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        Self::Function {
+            id: id.into(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+            },
+        }
+    }
+
+    /// The ID of the call, which the tool message carrying its result must reference.
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Function { id, .. } => id,
+        }
+    }
+}
+
+/// The function, and arguments to it, that the assistant called.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall<'a> {
+    pub name: Cow<'a, str>,
+    pub arguments: Cow<'a, str>,
+}
+
+/// Creates a tool message carrying the result of the tool call with the given ID, like so:
+/// `tool_message!(tool_call_id, result)`
+#[macro_export]
+macro_rules! tool_message {
+    ($tool_call_id:expr, $content:expr $(,)?) => {
+        ::kind_openai::endpoints::chat::Message::role(::kind_openai::endpoints::chat::Role::Tool)
+            .tool_call_id(::std::convert::Into::into($tool_call_id))
+            .content(::std::convert::Into::into($content))
+            .build()
+    };
+}
+
 /// A chat completion response message. Don't use this type directly, and instead use the
 /// `?` AKA `Try` operator to convert it into a result that can be used.
 pub struct UnifiedChatCompletionResponseMessage<T> {
-    content: T,
-    refusal: Option<String>,
+    pub(super) content: T,
+    pub(super) refusal: Option<String>,
 }
 
 impl<T> From<UnifiedChatCompletionResponseMessage<T>> for OpenAIResult<T> {
@@ -104,8 +260,81 @@ impl<T> From<UnifiedChatCompletionResponseMessage<T>> for OpenAIResult<T> {
     }
 }
 
+/// What every kind of completion response has in common, so that standard, structured, and reasoning
+/// completions can be inspected the same way. Everything is read from the first choice, and is `None`
+/// when the response has no choices.
+pub trait CompletionResponse {
+    /// Why the model stopped generating the first choice.
+    fn finish_reason(&self) -> Option<FinishReason>;
+
+    /// The refusal of the first choice, if the model refused.
+    fn refusal(&self) -> Option<&str>;
+
+    /// The usage tokens of the response, if the provider reported them.
+    fn usage(&self) -> Option<Usage>;
+}
+
+// the accessors that standard and structured responses share, since their fields only differ in the type of
+// their choices. written once so that the two can't drift apart.
+macro_rules! response_accessors {
+    () => {
+        /// The ID of the completion, which can be used to retrieve it later if it was created with `store`.
+        /// Empty if the provider didn't report it.
+        pub fn id(&self) -> &str {
+            &self.id
+        }
+
+        /// The unix timestamp (in seconds) of when the completion was created, or zero if the provider didn't
+        /// report it.
+        pub fn created(&self) -> i64 {
+            self.created
+        }
+
+        /// The exact model that generated the completion, for example `gpt-4o-2024-08-06` when `gpt-4o` was requested.
+        /// Empty if the provider didn't report it.
+        pub fn model(&self) -> &str {
+            &self.model
+        }
+
+        /// Identifies the backend configuration that generated the completion. Together with `seed`, this is
+        /// what determines whether repeated requests give the same response.
+        pub fn system_fingerprint(&self) -> Option<&str> {
+            self.system_fingerprint.as_deref()
+        }
+
+        /// Whether the two responses were generated by the same model under the same backend configuration,
+        /// meaning that requests with the same seed and parameters should have given the same response. Responses
+        /// without a fingerprint can't be compared and are never considered reproducible.
+        pub fn is_reproducible_with(&self, other: &Self) -> bool {
+            self.model == other.model
+                && self.system_fingerprint.is_some()
+                && self.system_fingerprint == other.system_fingerprint
+        }
+
+        /// Gives the usage tokens of the response, if the provider reported them.
+        pub fn usage(&self) -> Option<$crate::Usage> {
+            self.usage
+        }
+
+        /// The estimated number of completion tokens spent on each choice. See `Usage::completion_tokens_per_choice`.
+        pub fn completion_tokens_per_choice(&self) -> Option<f64> {
+            self.usage
+                .as_ref()
+                .map(|usage| usage.completion_tokens_per_choice(self.choices.len()))
+        }
+    };
+}
+
+pub(crate) use response_accessors;
+
+// what responses that unexpectedly contain no choices are reported as, since there's no content to parse.
+pub(super) fn no_choices_error() -> OpenAIError {
+    let err = <serde_json::Error as serde::de::Error>::custom("the response contained no choices");
+    OpenAIError::Serde(String::new(), err)
+}
+
 /// The reason the response was terminated.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum FinishReason {
     Stop,
@@ -113,3 +342,140 @@ pub enum FinishReason {
     ContentFilter,
     ToolCalls,
 }
+
+/// The log probabilities of the tokens generated for a choice, present when `logprobs` is enabled.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChoiceLogprobs {
+    content: Option<Vec<TokenLogprob>>,
+    refusal: Option<Vec<TokenLogprob>>,
+}
+
+impl ChoiceLogprobs {
+    /// The log probabilities of each token of the message content.
+    pub fn content(&self) -> &[TokenLogprob] {
+        self.content.as_deref().unwrap_or_default()
+    }
+
+    /// The log probabilities of each token of the refusal.
+    pub fn refusal(&self) -> &[TokenLogprob] {
+        self.refusal.as_deref().unwrap_or_default()
+    }
+
+    /// The mean log probability across all of the content tokens, or `None` if there are none.
+    pub fn average_content_logprob(&self) -> Option<f64> {
+        let content = self.content();
+        if content.is_empty() {
+            return None;
+        }
+
+        Some(content.iter().map(|token| token.logprob).sum::<f64>() / content.len() as f64)
+    }
+}
+
+/// The log probability of a single generated token.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Option<Vec<u8>>,
+    /// The most likely alternatives for this token, if `top_logprobs` was requested.
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// One of the most likely alternatives for a generated token.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// An annotation attached to the content of a message, such as a citation produced by web search.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    UrlCitation {
+        url_citation: UrlCitation,
+    },
+    /// An annotation that this crate doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+/// A citation of a web page, covering the part of the message content between `start_index` and `end_index`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct UrlCitation {
+    pub url: String,
+    pub title: String,
+    pub start_index: usize,
+    pub end_index: usize,
+}
+
+/// The results of the content filters that were run over a choice. These are only produced by some
+/// providers, most notably Azure OpenAI.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ContentFilterResults {
+    pub hate: Option<ContentFilterSeverityResult>,
+    pub self_harm: Option<ContentFilterSeverityResult>,
+    pub sexual: Option<ContentFilterSeverityResult>,
+    pub violence: Option<ContentFilterSeverityResult>,
+    pub profanity: Option<ContentFilterDetectionResult>,
+    pub jailbreak: Option<ContentFilterDetectionResult>,
+    pub protected_material_text: Option<ContentFilterDetectionResult>,
+    pub protected_material_code: Option<ContentFilterDetectionResult>,
+}
+
+impl ContentFilterResults {
+    /// The names of the categories that caused the content to be filtered.
+    pub fn filtered_categories(&self) -> Vec<&'static str> {
+        [
+            ("hate", self.hate.map(|result| result.filtered)),
+            ("self_harm", self.self_harm.map(|result| result.filtered)),
+            ("sexual", self.sexual.map(|result| result.filtered)),
+            ("violence", self.violence.map(|result| result.filtered)),
+            ("profanity", self.profanity.map(|result| result.filtered)),
+            ("jailbreak", self.jailbreak.map(|result| result.filtered)),
+            (
+                "protected_material_text",
+                self.protected_material_text.map(|result| result.filtered),
+            ),
+            (
+                "protected_material_code",
+                self.protected_material_code.map(|result| result.filtered),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, filtered)| filtered.unwrap_or(false))
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// The result of a content filter category that is graded by severity.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ContentFilterSeverityResult {
+    pub filtered: bool,
+    pub severity: ContentFilterSeverity,
+}
+
+/// The result of a content filter category that is either detected or not.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ContentFilterDetectionResult {
+    pub filtered: bool,
+    pub detected: bool,
+}
+
+/// How severe the content in a content filter category was judged to be.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterSeverity {
+    Safe,
+    Low,
+    Medium,
+    High,
+    /// A severity that this crate doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A kind of output that the model can generate. Audio is only supported by the audio models, such as
+/// `Model::Gpt4oAudioPreview`, and requires `audio` to be set on the request too.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Modality {
+    Text,
+    Audio,
+}
+
+/// How the model should speak when `Modality::Audio` is requested.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioConfig {
+    pub voice: Voice,
+    pub format: AudioFormat,
+}
+
+/// The voice the model speaks with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Voice {
+    Alloy,
+    Ash,
+    Ballad,
+    Coral,
+    Echo,
+    Sage,
+    Shimmer,
+    Verse,
+}
+
+/// The encoding of the audio the model generates.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    Flac,
+    Opus,
+    Pcm16,
+}
+
+/// The audio the model generated in response, alongside a transcript of it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ResponseAudio {
+    id: String,
+    #[serde(deserialize_with = "de_base64")]
+    data: Vec<u8>,
+    expires_at: i64,
+    transcript: String,
+}
+
+impl ResponseAudio {
+    /// The ID of the audio, which lets a later request in the same conversation refer back to it.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The audio itself, in the format that was requested.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consumes the audio and gives its bytes.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// The unix timestamp (in seconds) after which the audio can no longer be referred back to.
+    pub fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    pub fn transcript(&self) -> &str {
+        &self.transcript
+    }
+}
+
+/// Audio that the model generated earlier in the conversation, referred to by ID so that an assistant message
+/// in the history can carry it without sending the audio back. Only valid until the audio expires.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AudioReference<'a> {
+    pub id: Cow<'a, str>,
+}
+
+// the audio arrives base64 encoded, which is decoded up front so that malformed audio fails like any other
+// malformed response would.
+fn de_base64<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let data = String::deserialize(deserializer)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Message, Role};
+
+/// The content of a chat completion message, which is either plain text or a list of parts for
+/// multimodal messages (such as text alongside images).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum MessageContent<'a> {
+    Text(Cow<'a, str>),
+    Parts(Vec<ContentPart<'a>>),
+}
+
+impl MessageContent<'_> {
+    /// The text of the content, with the text of every part joined by newlines for multimodal content.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            MessageContent::Text(text) => Cow::Borrowed(text),
+            MessageContent::Parts(parts) => Cow::Owned(
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_ref()),
+                        ContentPart::ImageUrl { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for MessageContent<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        MessageContent::Text(value)
+    }
+}
+
+impl<'a> From<&'a str> for MessageContent<'a> {
+    fn from(value: &'a str) -> Self {
+        MessageContent::Text(value.into())
+    }
+}
+
+impl From<String> for MessageContent<'_> {
+    fn from(value: String) -> Self {
+        MessageContent::Text(value.into())
+    }
+}
+
+impl<'a> From<Vec<ContentPart<'a>>> for MessageContent<'a> {
+    fn from(value: Vec<ContentPart<'a>>) -> Self {
+        MessageContent::Parts(value)
+    }
+}
+
+/// A single part of a multimodal message.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart<'a> {
+    Text { text: Cow<'a, str> },
+    ImageUrl { image_url: ImageUrl<'a> },
+}
+
+impl<'a> From<ImageSource<'a>> for ContentPart<'a> {
+    fn from(value: ImageSource<'a>) -> Self {
+        ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: value.into_url(),
+            },
+        }
+    }
+}
+
+/// The URL of an image, which may also be a `data:` URI containing the image itself.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageUrl<'a> {
+    url: Cow<'a, str>,
+}
+
+impl ImageUrl<'_> {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Where the model should get an image from.
+#[derive(Debug, Clone)]
+pub enum ImageSource<'a> {
+    /// An image hosted at a URL that OpenAI can reach.
+    Url(Cow<'a, str>),
+    /// An image sent inline, as base64 encoded data of the provided MIME type (for example `image/png`).
+    Base64 {
+        data: Cow<'a, str>,
+        mime: Cow<'a, str>,
+    },
+}
+
+impl<'a> ImageSource<'a> {
+    fn into_url(self) -> Cow<'a, str> {
+        match self {
+            ImageSource::Url(url) => url,
+            ImageSource::Base64 { data, mime } => format!("data:{mime};base64,{data}").into(),
+        }
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Creates a user message that contains both text and an image, for models that support vision.
+    pub fn user_with_image(text: impl Into<Cow<'a, str>>, image: ImageSource<'a>) -> Self {
+        Message::role(Role::User)
+            .content(MessageContent::Parts(vec![
+                ContentPart::Text { text: text.into() },
+                image.into(),
+            ]))
+            .build()
+    }
+}
@@ -0,0 +1,94 @@
+use bon::builder;
+use kind_openai_schema::OpenAISchema;
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    auth::AuthTokenProvider, endpoints::OpenAIRequestProvider, OpenAI, OpenAIError, OpenAIResult,
+};
+
+use super::{no_choices_error, ChatCompletionResponse, Message, Role, StructuredChatCompletion};
+
+/// The prompt sent by `req_with_repair` when the model's output fails to deserialize. `{error}` is
+/// replaced with the deserialization error.
+pub const DEFAULT_REPAIR_PROMPT: &str = "Your previous response could not be parsed: {error}. \
+Respond again with only JSON that matches the schema exactly.";
+
+/// Sends a structured chat completion, and if the model's output fails to deserialize into your
+/// desired type, asks the model to fix it by replying with its own output and the repair prompt,
+/// up to `max_repairs` times. Refusals are never repaired.
+///
+/// Call like so: `req_with_repair(&client, &request).max_repairs(2).call().await`
+#[builder]
+pub async fn req_with_repair<Auth, S>(
+    #[builder(start_fn)] client: &OpenAI<Auth>,
+    #[builder(start_fn)] request: &StructuredChatCompletion<'_, S>,
+    /// How many follow-up requests may be sent to repair the output. Defaults to 1.
+    #[builder(default = 1)]
+    max_repairs: usize,
+    /// The prompt that asks the model to fix its output, where `{error}` is replaced with the
+    /// deserialization error. Defaults to `DEFAULT_REPAIR_PROMPT`.
+    #[builder(into, default = DEFAULT_REPAIR_PROMPT.to_owned())]
+    repair_prompt: String,
+) -> OpenAIResult<S>
+where
+    Auth: AuthTokenProvider,
+    S: OpenAISchema + DeserializeOwned,
+{
+    let mut request = request.clone();
+
+    for repair in 0..=max_repairs {
+        let content = client
+            .req(&RawStructuredChatCompletion(&request))
+            .await?
+            .take_first_choice()
+            .ok_or_else(no_choices_error)?
+            .message()?;
+
+        let err = match serde_json::from_str::<S>(&content) {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        if repair == max_repairs {
+            return Err(OpenAIError::Serde(content, err));
+        }
+
+        let mut messages = request.base_request.messages().to_vec();
+        messages.push(
+            Message::role(Role::Assistant)
+                .content(content.into())
+                .build(),
+        );
+        messages.push(
+            Message::role(Role::User)
+                .content(repair_prompt.replace("{error}", &err.to_string()).into())
+                .build(),
+        );
+        request = request.with_messages(messages);
+    }
+
+    unreachable!("the last attempt always returns")
+}
+
+// sends a structured request but leaves the content as a raw string, so that a failure to deserialize
+// it doesn't lose the output that needs repairing.
+#[derive(Serialize)]
+#[serde(transparent, bound = "")]
+struct RawStructuredChatCompletion<'r, 'a, S>(&'r StructuredChatCompletion<'a, S>);
+
+impl<S> OpenAIRequestProvider for RawStructuredChatCompletion<'_, '_, S> {
+    type Response = ChatCompletionResponse;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/chat/completions".to_string()
+    }
+
+    fn validate(&self) -> OpenAIResult<()> {
+        self.0.base_request.validate()
+    }
+}
+
+impl<S> super::super::private::Sealed for RawStructuredChatCompletion<'_, '_, S> {}
@@ -6,26 +6,240 @@ use kind_openai_schema::OpenAISchema;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-use crate::{endpoints::OpenAIRequestProvider, OpenAIResult, Usage};
+use crate::{
+    endpoints::{
+        estimate_tokens, sampling::sampling_setters, OpenAIRequestProvider, SamplingParams,
+    },
+    OpenAIError, OpenAIResult, Usage,
+};
 
 use super::{
+    response_accessors,
     structured::{ChatCompletionRequestResponseFormat, StructuredChatCompletion},
-    FinishReason, Message, Model, UnifiedChatCompletionResponseMessage,
+    Annotation, AudioConfig, AudioReference, ChoiceLogprobs, CompletionResponse,
+    ContentFilterResults, FinishReason, Message, Modality, Model, ResponseAudio, Role, Tool,
+    ToolCall, UnifiedChatCompletionResponseMessage, UrlCitation,
 };
 
 /// A standard chat completion request. The response will be a string in any shape and will not
 /// be parsed.
-#[derive(Serialize, Builder)]
-#[builder(start_fn = model, finish_fn = unstructured, state_mod(vis = "pub"))]
+#[derive(Serialize, Deserialize, Clone, Builder)]
+#[builder(
+    start_fn = model,
+    finish_fn(name = build_unvalidated, vis = ""),
+    state_mod(vis = "pub")
+)]
 pub struct ChatCompletion<'a> {
     #[builder(start_fn)]
     model: Model,
+    #[serde(flatten)]
+    #[builder(field)]
+    sampling: SamplingParams,
+    #[serde(borrow)]
     messages: Vec<Message<'a>>,
-    temperature: Option<f32>,
-    top_p: Option<f32>,
+    /// How many choices to generate. Every choice is billed, so prefer one unless you need alternatives.
+    n: Option<u8>,
+    /// The maximum number of tokens to generate, including any reasoning tokens.
+    max_completion_tokens: Option<u32>,
     store: Option<bool>,
     metadata: Option<HashMap<String, String>>,
-    logit_bias: Option<HashMap<i32, i32>>,
+    /// Groups requests that share a long prefix (such as the same system prompt) so that they're routed
+    /// to the same prompt cache, which improves cache hit rates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(into)]
+    prompt_cache_key: Option<String>,
+    /// A stable identifier for the end user the request is made on behalf of, such as a hash of their
+    /// username, which OpenAI uses to detect abuse without needing any identifying information.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(into)]
+    safety_identifier: Option<String>,
+    /// The kinds of output to generate, which defaults to text only. Audio also requires `audio` to be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(into)]
+    modalities: Option<Vec<Modality>>,
+    /// The voice and format of the audio to generate, when `Modality::Audio` is requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio: Option<AudioConfig>,
+    /// The functions that the model may call, whose calls come back on the message as `tool_calls`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+    /// Whether or not to return the log probabilities of each generated token.
+    logprobs: Option<bool>,
+    /// The number of most likely alternatives to return for each token, between 0 and 20. Requires `logprobs`.
+    top_logprobs: Option<u8>,
+    /// Opts into a best-effort check, before the request is sent, that the messages don't obviously
+    /// overflow the model's context window. The check uses a rough estimate of ~4 characters per token,
+    /// so it only catches prompts that are clearly too large.
+    #[serde(skip)]
+    #[builder(default)]
+    check_context_window: bool,
+    /// A JSON object of extra fields to merge into the request body, for adopting parameters that this crate
+    /// doesn't support yet. Typed fields that are set take precedence over extra fields with the same name.
+    ///
+    /// The extra fields are only merged in when the request is sent, and aren't part of the request's own
+    /// serialized form. A request that's persisted and restored through serde comes back without them, so
+    /// they need setting again with `with_extra_body`.
+    #[serde(skip)]
+    extra_body: Option<serde_json::Value>,
+}
+
+// an arbitrary but fixed seed, so that deterministic requests agree with each other across runs.
+const DETERMINISTIC_SEED: i64 = 0;
+
+impl<'a> ChatCompletion<'a> {
+    /// Starts building a request preset for tasks like classification, where the same input should give
+    /// the same output: a `temperature` of 0, a `top_p` of 1, and a fixed `seed`. The presets can be
+    /// overridden once the request is built with `with_temperature`, `with_top_p`, and `with_seed`.
+    pub fn deterministic(model: Model) -> ChatCompletionBuilder<'a> {
+        ChatCompletion::model(model)
+            .temperature(0.0)
+            .top_p(1.0)
+            .seed(DETERMINISTIC_SEED)
+    }
+
+    /// Replaces the temperature, keeping the rest of the configuration.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.sampling.temperature = Some(temperature);
+        self
+    }
+
+    /// Replaces `top_p`, keeping the rest of the configuration.
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.sampling.top_p = Some(top_p);
+        self
+    }
+
+    /// Replaces the seed, keeping the rest of the configuration.
+    pub fn with_seed(mut self, seed: i64) -> Self {
+        self.sampling.seed = Some(seed);
+        self
+    }
+
+    /// Replaces the messages, keeping the rest of the configuration. Useful for building a request once and
+    /// firing (a clone of) it repeatedly with different inputs.
+    pub fn with_messages(self, messages: Vec<Message<'a>>) -> Self {
+        Self { messages, ..self }
+    }
+
+    /// Replaces the model, keeping the rest of the configuration. Useful for comparing how different models
+    /// handle the same prompt.
+    pub fn with_model(self, model: Model) -> Self {
+        Self { model, ..self }
+    }
+
+    /// Replaces the extra fields merged into the request body, keeping the rest of the configuration. Needed
+    /// to restore them on a request that was deserialized, since they aren't part of its serialized form.
+    pub fn with_extra_body(self, extra_body: serde_json::Value) -> Self {
+        Self {
+            extra_body: Some(extra_body),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "tokenizer")]
+    pub(super) fn chat_model(&self) -> Model {
+        self.model
+    }
+
+    pub(super) fn messages(&self) -> &[Message<'a>] {
+        &self.messages
+    }
+
+    pub(super) fn extra_body(&self) -> Option<&serde_json::Value> {
+        self.extra_body.as_ref()
+    }
+
+    /// Checks that there is at least one message that isn't a system or developer message, and that the sampling
+    /// parameters are within the ranges accepted by OpenAI: 0 to 2 for `temperature`, 0 to 1 for `top_p`,
+    /// and -2 to 2 for the penalties.
+    /// If `check_context_window` is enabled, this also checks the estimated size of the messages.
+    /// This is run automatically when the request is built, and again before it's sent.
+    pub fn validate(&self) -> OpenAIResult<()> {
+        if self.messages.is_empty() {
+            return Err(OpenAIError::Validation(
+                "at least one message must be provided".to_string(),
+            ));
+        }
+
+        if self
+            .messages
+            .iter()
+            .all(|message| matches!(message.role, Role::System | Role::Developer))
+        {
+            return Err(OpenAIError::Validation(
+                "at least one message must be a user or assistant message".to_string(),
+            ));
+        }
+
+        if self
+            .messages
+            .iter()
+            .any(|message| matches!(message.role, Role::Tool) && message.tool_call_id.is_none())
+        {
+            return Err(OpenAIError::Validation(
+                "tool messages must have a tool_call_id".to_string(),
+            ));
+        }
+
+        if self.messages.iter().any(|message| {
+            message.content.is_none()
+                && !(matches!(message.role, Role::Assistant)
+                    && (message.tool_calls.is_some()
+                        || message.refusal.is_some()
+                        || message.audio.is_some()))
+        }) {
+            return Err(OpenAIError::Validation(
+                "only assistant messages that make tool calls, refuse, or speak audio may leave out their content"
+                    .to_string(),
+            ));
+        }
+
+        self.sampling.validate()?;
+
+        if self
+            .extra_body
+            .as_ref()
+            .is_some_and(|extra| !extra.is_object())
+        {
+            return Err(OpenAIError::Validation(
+                "extra_body must be a JSON object".to_string(),
+            ));
+        }
+
+        if self.check_context_window {
+            let estimated_tokens = self
+                .messages
+                .iter()
+                .filter_map(|message| message.content.as_ref())
+                .map(|content| estimate_tokens(&content.text()))
+                .sum::<usize>();
+            let context_window = self.model.context_window();
+            if estimated_tokens > context_window as usize {
+                return Err(OpenAIError::Validation(format!(
+                    "the messages are an estimated {estimated_tokens} tokens, which exceeds the model's context window of {context_window} tokens"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like `validate`, but additionally rejects setting both `temperature` and `top_p`. OpenAI recommends
+    /// altering one or the other but not both, though it will accept requests that do. A `top_p` of 1 is
+    /// OpenAI's default and doesn't count as altering it.
+    pub fn validate_strict(&self) -> OpenAIResult<()> {
+        self.validate()?;
+
+        if self.sampling.temperature.is_some()
+            && self.sampling.top_p.is_some_and(|top_p| top_p != 1.0)
+        {
+            return Err(OpenAIError::Validation(
+                "only one of temperature or top_p should be set".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl OpenAIRequestProvider for ChatCompletion<'_> {
@@ -33,62 +247,213 @@ impl OpenAIRequestProvider for ChatCompletion<'_> {
 
     const METHOD: Method = Method::POST;
 
-    fn path_with_leading_slash() -> String {
+    fn path_with_leading_slash(&self) -> String {
         "/chat/completions".to_string()
     }
+
+    fn validate(&self) -> OpenAIResult<()> {
+        ChatCompletion::validate(self)
+    }
+
+    fn extra_body(&self) -> Option<&serde_json::Value> {
+        ChatCompletion::extra_body(self)
+    }
 }
 
 impl super::super::private::Sealed for ChatCompletion<'_> {}
 
+impl<S> ChatCompletionBuilder<'_, S>
+where
+    S: chat_completion_builder::State,
+{
+    sampling_setters!();
+}
+
+impl<'a, S> ChatCompletionBuilder<'a, S>
+where
+    S: chat_completion_builder::State,
+    S::MaxCompletionTokens: chat_completion_builder::IsUnset,
+{
+    /// Sets `max_completion_tokens`, which is what OpenAI renamed `max_tokens` to. Newer models reject
+    /// `max_tokens` entirely, so it's never sent.
+    #[deprecated(since = "0.3.10", note = "use `max_completion_tokens` instead")]
+    pub fn max_tokens(
+        self,
+        max_tokens: u32,
+    ) -> ChatCompletionBuilder<'a, chat_completion_builder::SetMaxCompletionTokens<S>> {
+        self.max_completion_tokens(max_tokens)
+    }
+}
+
 // this is a neat trick where we can take a completed builder and allow it to be "upgraded".
-// because of the `finish_fn` specification, we can either resolve and build immediately with
-// `.unstructured()`, or we can call `.structured()` and provide a schema. doing it this way
-// enables us to nicely represent the `ChatCompletionRequest` without having to specify the
-// generic type.
+// the generated `finish_fn` is kept private so that every way of finishing goes through `validate`: we can
+// either resolve and build immediately with `.unstructured()`, or we can call `.structured()` and provide a
+// schema. doing it this way enables us to nicely represent the `ChatCompletionRequest` without having to
+// specify the generic type.
 impl<'a, S> ChatCompletionBuilder<'a, S>
 where
     S: IsComplete,
 {
-    /// Upgrades a chat completion request to a structured chat completion request.
-    /// Unless the return type can be inferred, you probably want to call this like so:
+    /// Builds the request, checking it with `validate` so that a request which OpenAI would reject can't be
+    /// built in the first place.
+    pub fn unstructured(self) -> OpenAIResult<ChatCompletion<'a>> {
+        let request = self.build_unvalidated();
+        request.validate()?;
+        Ok(request)
+    }
+
+    /// Upgrades a chat completion request to a structured chat completion request, checking it with
+    /// `validate` like `unstructured` does. Unless the return type can be inferred, you probably want to
+    /// call this like so:
     /// `.structured::<MySchemadType>();`
-    pub fn structured<SS>(self) -> StructuredChatCompletion<'a, SS>
+    ///
+    /// The type must derive both `OpenAISchema` and `serde::Deserialize`, and forgetting either one is a
+    /// compile error that says so.
+    pub fn structured<SS>(self) -> OpenAIResult<StructuredChatCompletion<'a, SS>>
     where
         SS: OpenAISchema,
     {
-        StructuredChatCompletion {
-            base_request: self.unstructured(),
-            response_format: ChatCompletionRequestResponseFormat::JsonSchema(SS::openai_schema()),
+        Ok(StructuredChatCompletion {
+            base_request: self.unstructured()?,
+            response_format: ChatCompletionRequestResponseFormat::of::<SS>(),
             _phantom: std::marker::PhantomData,
-        }
+        })
+    }
+
+    /// Like `structured`, but overrides the name of the schema sent to OpenAI, and its description too if
+    /// one is provided, without changing the type. Useful for A/B testing schema descriptions. The overrides
+    /// aren't kept if the request is serialized and deserialized again.
+    pub fn structured_named<SS>(
+        self,
+        name: &str,
+        description: Option<&str>,
+    ) -> OpenAIResult<StructuredChatCompletion<'a, SS>>
+    where
+        SS: OpenAISchema,
+    {
+        Ok(StructuredChatCompletion {
+            base_request: self.unstructured()?,
+            response_format: ChatCompletionRequestResponseFormat::named::<SS>(name, description),
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Like `structured`, but replaces the description of the schema sent to OpenAI (which otherwise comes
+    /// from the docstring of the type), such as for a localized description. The override isn't kept if the
+    /// request is serialized and deserialized again.
+    pub fn structured_with_description<SS>(
+        self,
+        description: &str,
+    ) -> OpenAIResult<StructuredChatCompletion<'a, SS>>
+    where
+        SS: OpenAISchema,
+    {
+        Ok(StructuredChatCompletion {
+            base_request: self.unstructured()?,
+            response_format: ChatCompletionRequestResponseFormat::described::<SS>(description),
+            _phantom: std::marker::PhantomData,
+        })
     }
 }
 
 /// A response from a chat completion request.
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct ChatCompletionResponse {
+    // OpenAI always sends these, but some OpenAI-compatible providers leave them out, which shouldn't make
+    // the whole response fail to deserialize.
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    created: i64,
+    #[serde(default)]
+    model: String,
+    system_fingerprint: Option<String>,
     choices: Vec<ChatCompletionResponseChoice>,
-    usage: Usage,
+    // some proxies (such as LiteLLM and Azure) occasionally leave out the usage block, which shouldn't make
+    // the whole response fail to deserialize.
+    usage: Option<Usage>,
+    // everything this crate doesn't know about yet, kept around so that new API fields can be inspected
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
 }
 
 impl ChatCompletionResponse {
+    response_accessors!();
+
+    /// Every field of the response that isn't otherwise exposed, such as `object`, `service_tier`, or any
+    /// field the API has added since this crate was last updated. Useful for debugging and for reading
+    /// new fields before they're supported.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+
     /// Takes the first message in the response consumes the response.
     pub fn take_first_choice(self) -> Option<ChatCompletionResponseChoice> {
         self.choices.into_iter().next()
     }
 
-    /// Gives the usage tokens of the response.
-    pub fn usage(&self) -> &Usage {
-        &self.usage
+    /// Consumes the response and gives the message of the first choice alongside the usage, which is
+    /// otherwise awkward to hold on to once the choice has been taken. The usage is `None` if the provider
+    /// didn't report it.
+    pub fn into_first_message(self) -> OpenAIResult<(String, Option<Usage>)> {
+        let usage = self.usage;
+        let message = self
+            .take_first_choice()
+            .ok_or_else(super::no_choices_error)?
+            .message()?;
+        Ok((message, usage))
+    }
+
+    pub fn choices(&self) -> &[ChatCompletionResponseChoice] {
+        &self.choices
+    }
+
+    /// Consumes the response and gives every choice, for when `n` was greater than one.
+    pub fn take_choices(self) -> Vec<ChatCompletionResponseChoice> {
+        self.choices
+    }
+}
+
+impl CompletionResponse for ChatCompletionResponse {
+    fn finish_reason(&self) -> Option<FinishReason> {
+        self.choices.first().map(|choice| choice.finish_reason)
+    }
+
+    fn refusal(&self) -> Option<&str> {
+        self.choices.first()?.message.refusal.as_deref()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+impl IntoIterator for ChatCompletionResponse {
+    type Item = ChatCompletionResponseChoice;
+    type IntoIter = std::vec::IntoIter<ChatCompletionResponseChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ChatCompletionResponse {
+    type Item = &'a ChatCompletionResponseChoice;
+    type IntoIter = std::slice::Iter<'a, ChatCompletionResponseChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.iter()
     }
 }
 
 /// A response choice from a chat completion request.
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct ChatCompletionResponseChoice {
     finish_reason: FinishReason,
     index: i32,
     message: ChatCompletionResponseMessage,
+    logprobs: Option<ChoiceLogprobs>,
+    content_filter_results: Option<ContentFilterResults>,
 }
 
 impl ChatCompletionResponseChoice {
@@ -104,19 +469,77 @@ impl ChatCompletionResponseChoice {
     pub fn index(&self) -> i32 {
         self.index
     }
+
+    /// The log probabilities of the generated tokens, if `logprobs` was enabled on the request.
+    pub fn logprobs(&self) -> Option<&ChoiceLogprobs> {
+        self.logprobs.as_ref()
+    }
+
+    /// The results of the content filters run over the choice, for providers that report them (such as Azure).
+    pub fn content_filter_results(&self) -> Option<&ContentFilterResults> {
+        self.content_filter_results.as_ref()
+    }
+
+    /// The audio the model generated, if `Modality::Audio` was requested. The text of an audio response is
+    /// its transcript rather than the message content.
+    pub fn audio(&self) -> Option<&ResponseAudio> {
+        self.message.audio.as_ref()
+    }
+
+    /// The annotations attached to the message, such as the sources it cites when web search is used.
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.message.annotations
+    }
+
+    /// Turns the choice into an assistant message carrying its content, refusal, tool calls, and a reference
+    /// to its audio, so that it can be appended to the history of the next request.
+    pub fn into_message(self) -> Message<'static> {
+        let ChatCompletionResponseMessage {
+            content,
+            refusal,
+            tool_calls,
+            audio,
+            ..
+        } = self.message;
+
+        Message::role(Role::Assistant)
+            .maybe_content(content.map(Into::into))
+            .maybe_refusal(refusal.map(Into::into))
+            .maybe_tool_calls(tool_calls)
+            .maybe_audio(audio.map(|audio| AudioReference {
+                id: audio.id().to_owned().into(),
+            }))
+            .build()
+    }
+
+    /// The web pages cited by the message, in the order they're cited.
+    pub fn url_citations(&self) -> impl Iterator<Item = &UrlCitation> {
+        self.message
+            .annotations
+            .iter()
+            .filter_map(|annotation| match annotation {
+                Annotation::UrlCitation { url_citation } => Some(url_citation),
+                Annotation::Other => None,
+            })
+    }
 }
 
 // leave private, messages should only be interacted with through the unified message type.
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 struct ChatCompletionResponseMessage {
-    content: String,
+    // null when the model refused
+    content: Option<String>,
     refusal: Option<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    tool_calls: Option<Vec<ToolCall<'static>>>,
+    audio: Option<ResponseAudio>,
 }
 
 impl From<ChatCompletionResponseMessage> for UnifiedChatCompletionResponseMessage<String> {
     fn from(value: ChatCompletionResponseMessage) -> Self {
         UnifiedChatCompletionResponseMessage {
-            content: value.content,
+            content: value.content.unwrap_or_default(),
             refusal: value.refusal,
         }
     }
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use bon::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::endpoints::{OpenAIRequestProvider, Page};
+
+use super::ChatCompletionResponse;
+
+/// A request to retrieve a chat completion that was created with `store` enabled.
+#[derive(Serialize, Debug, Clone)]
+pub struct RetrieveStoredCompletion<'a> {
+    #[serde(skip)]
+    id: &'a str,
+}
+
+impl<'a> RetrieveStoredCompletion<'a> {
+    /// Creates a request to retrieve the stored completion with the provided ID.
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
+    }
+}
+
+impl OpenAIRequestProvider for RetrieveStoredCompletion<'_> {
+    type Response = StoredCompletion;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        format!("/chat/completions/{}", self.id)
+    }
+}
+
+impl super::super::private::Sealed for RetrieveStoredCompletion<'_> {}
+
+/// A chat completion that was retained by OpenAI, alongside the metadata it was created with.
+#[derive(Deserialize, Clone, Debug)]
+pub struct StoredCompletion {
+    #[serde(flatten)]
+    completion: ChatCompletionResponse,
+    #[serde(default)]
+    metadata: Option<HashMap<String, String>>,
+}
+
+impl StoredCompletion {
+    pub fn id(&self) -> &str {
+        self.completion.id()
+    }
+
+    /// The unix timestamp (in seconds) of when the completion was created.
+    pub fn created(&self) -> i64 {
+        self.completion.created()
+    }
+
+    pub fn metadata(&self) -> Option<&HashMap<String, String>> {
+        self.metadata.as_ref()
+    }
+
+    pub fn completion(&self) -> &ChatCompletionResponse {
+        &self.completion
+    }
+
+    /// Consumes the stored completion and gives the completion itself.
+    pub fn into_completion(self) -> ChatCompletionResponse {
+        self.completion
+    }
+}
+
+/// A request to list the chat completions that were created with `store` enabled, a page at a time.
+///
+/// To fetch the next page, pass the previous page's `next_cursor` as `after`.
+#[derive(Serialize, Debug, Clone, Default, Builder)]
+pub struct ListStoredCompletions<'a> {
+    /// Only lists completions whose metadata contains every one of these key/value pairs.
+    #[serde(skip)]
+    metadata: Option<HashMap<&'a str, &'a str>>,
+    /// Only lists completions generated by this model.
+    #[serde(skip)]
+    model: Option<&'a str>,
+    /// The ID of the completion to start listing after.
+    #[serde(skip)]
+    after: Option<&'a str>,
+    /// The number of completions to list, between 1 and 100. OpenAI defaults to 20.
+    #[serde(skip)]
+    limit: Option<u32>,
+}
+
+impl OpenAIRequestProvider for ListStoredCompletions<'_> {
+    type Response = StoredCompletionList;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/chat/completions".to_string()
+    }
+
+    fn query_params(&self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        if let Some(metadata) = &self.metadata {
+            let mut metadata = metadata.iter().collect::<Vec<_>>();
+            // sorted so that the same filters always produce the same query
+            metadata.sort();
+            params.extend(
+                metadata
+                    .into_iter()
+                    .map(|(key, value)| (format!("metadata[{key}]"), value.to_string())),
+            );
+        }
+        if let Some(model) = self.model {
+            params.push(("model".to_owned(), model.to_owned()));
+        }
+        if let Some(after) = self.after {
+            params.push(("after".to_owned(), after.to_owned()));
+        }
+        if let Some(limit) = self.limit {
+            params.push(("limit".to_owned(), limit.to_string()));
+        }
+
+        params
+    }
+}
+
+impl super::super::private::Sealed for ListStoredCompletions<'_> {}
+
+/// A page of stored completions.
+pub type StoredCompletionList = Page<StoredCompletion>;
+
+impl Page<StoredCompletion> {
+    pub fn completions(&self) -> &[StoredCompletion] {
+        self.data()
+    }
+
+    /// Consumes the page and gives the completions.
+    pub fn into_completions(self) -> Vec<StoredCompletion> {
+        self.into_data()
+    }
+}
@@ -0,0 +1,242 @@
+use kind_openai_schema::OpenAISchema;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    endpoints::{stream::EventStream, OpenAIStreamingRequestProvider},
+    OpenAIError, OpenAIResult, Usage,
+};
+
+use super::{ChatCompletion, FinishReason, Role, StructuredChatCompletion};
+
+/// A single incremental chunk of a streamed chat completion.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChatCompletionChunk {
+    choices: Vec<ChatCompletionChunkChoice>,
+    usage: Option<Usage>,
+}
+
+impl ChatCompletionChunk {
+    /// The choices contained in this chunk. The final chunk of a stream carries the usage and no choices.
+    pub fn choices(&self) -> &[ChatCompletionChunkChoice] {
+        &self.choices
+    }
+
+    /// Gives the usage tokens of the entire response, which is only present on the final chunk.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+/// A streamed choice, containing the delta since the previous chunk.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChatCompletionChunkChoice {
+    index: i32,
+    delta: ChatCompletionChunkDelta,
+    finish_reason: Option<FinishReason>,
+}
+
+impl ChatCompletionChunkChoice {
+    pub fn index(&self) -> i32 {
+        self.index
+    }
+
+    pub fn delta(&self) -> &ChatCompletionChunkDelta {
+        &self.delta
+    }
+
+    /// The reason the response was terminated, only present on the last chunk of the choice.
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.finish_reason
+    }
+}
+
+/// The content that was generated since the previous chunk.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChatCompletionChunkDelta {
+    role: Option<Role>,
+    content: Option<String>,
+    refusal: Option<String>,
+}
+
+impl ChatCompletionChunkDelta {
+    pub fn role(&self) -> Option<Role> {
+        self.role
+    }
+
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    pub fn refusal(&self) -> Option<&str> {
+        self.refusal.as_deref()
+    }
+}
+
+/// A streamed chat completion. Call `next` until it returns `None` to consume the stream.
+pub struct ChatCompletionStream {
+    events: EventStream<ChatCompletionChunk>,
+}
+
+impl ChatCompletionStream {
+    /// Waits for the next chunk of the response, returning `None` once the stream has completed.
+    pub async fn next(&mut self) -> Option<OpenAIResult<ChatCompletionChunk>> {
+        self.events.next().await
+    }
+}
+
+impl OpenAIStreamingRequestProvider for ChatCompletion<'_> {
+    type Stream = ChatCompletionStream;
+
+    fn stream_from_response(response: reqwest::Response) -> Self::Stream {
+        ChatCompletionStream {
+            events: EventStream::new(response),
+        }
+    }
+}
+
+/// Accumulates the fragments of a streamed structured chat completion, deserializing the full JSON
+/// once the stream has completed. Only the first choice is accumulated.
+///
+/// You usually don't need to use this directly and can instead use the stream returned by
+/// streaming a `StructuredChatCompletion`, but it's available for when you're driving the chunks yourself.
+pub struct StructuredChatCompletionAccumulator<S> {
+    content: String,
+    refusal: Option<String>,
+    finish_reason: Option<FinishReason>,
+    usage: Option<Usage>,
+    _phantom: std::marker::PhantomData<S>,
+}
+
+impl<S> Default for StructuredChatCompletionAccumulator<S> {
+    fn default() -> Self {
+        Self {
+            content: String::new(),
+            refusal: None,
+            finish_reason: None,
+            usage: None,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<S> StructuredChatCompletionAccumulator<S>
+where
+    S: DeserializeOwned,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds a chunk into the accumulated response. Returns whether or not any new content was added.
+    pub fn push(&mut self, chunk: &ChatCompletionChunk) -> bool {
+        if let Some(usage) = chunk.usage {
+            self.usage = Some(usage);
+        }
+
+        let Some(choice) = chunk.choices.iter().find(|choice| choice.index == 0) else {
+            return false;
+        };
+
+        if let Some(finish_reason) = choice.finish_reason {
+            self.finish_reason = Some(finish_reason);
+        }
+
+        // refusals are streamed in fragments just like content is, so they need to be accumulated too
+        if let Some(refusal) = &choice.delta.refusal {
+            self.refusal
+                .get_or_insert_with(String::new)
+                .push_str(refusal);
+        }
+
+        match &choice.delta.content {
+            Some(content) if !content.is_empty() => {
+                self.content.push_str(content);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The raw, likely incomplete, JSON that has been accumulated so far.
+    pub fn snapshot(&self) -> &str {
+        &self.content
+    }
+
+    /// The refusal that has been accumulated so far, if the model has started refusing.
+    pub fn refusal(&self) -> Option<&str> {
+        self.refusal.as_deref()
+    }
+
+    /// The reason the response was terminated, if the final chunk of the first choice has been received.
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.finish_reason
+    }
+
+    /// The usage of the response, if the final chunk has been received.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+
+    /// Deserializes the accumulated JSON into your desired type, or gives the refusal if the model refused.
+    pub fn finish(self) -> OpenAIResult<S> {
+        if let Some(refusal) = self.refusal {
+            return Err(OpenAIError::Refusal(refusal));
+        }
+
+        if matches!(self.finish_reason, Some(FinishReason::Length)) {
+            return Err(OpenAIError::TruncatedStructuredOutput(self.content));
+        }
+
+        serde_json::from_str(&self.content).map_err(|err| OpenAIError::Serde(self.content, err))
+    }
+}
+
+/// A streamed structured chat completion, that yields snapshots of the raw JSON as it arrives
+/// and finishes with your desired type.
+pub struct StructuredChatCompletionStream<S> {
+    events: EventStream<ChatCompletionChunk>,
+    accumulator: StructuredChatCompletionAccumulator<S>,
+}
+
+impl<S> StructuredChatCompletionStream<S>
+where
+    S: DeserializeOwned,
+{
+    /// Waits for the next fragment of content, and gives a snapshot of all of the raw JSON that has been
+    /// accumulated so far. Returns `None` once the stream has completed.
+    pub async fn next_snapshot(&mut self) -> Option<OpenAIResult<&str>> {
+        loop {
+            match self.events.next().await? {
+                Ok(chunk) => {
+                    if self.accumulator.push(&chunk) {
+                        return Some(Ok(self.accumulator.snapshot()));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Consumes the rest of the stream and deserializes the full response into your desired type.
+    pub async fn finish(mut self) -> OpenAIResult<S> {
+        while let Some(chunk) = self.events.next().await {
+            self.accumulator.push(&chunk?);
+        }
+
+        self.accumulator.finish()
+    }
+}
+
+impl<S> OpenAIStreamingRequestProvider for StructuredChatCompletion<'_, S>
+where
+    S: OpenAISchema + DeserializeOwned,
+{
+    type Stream = StructuredChatCompletionStream<S>;
+
+    fn stream_from_response(response: reqwest::Response) -> Self::Stream {
+        StructuredChatCompletionStream {
+            events: EventStream::new(response),
+            accumulator: StructuredChatCompletionAccumulator::new(),
+        }
+    }
+}
@@ -1,19 +1,29 @@
 use kind_openai_schema::{GeneratedOpenAISchema, OpenAISchema};
-use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::{endpoints::OpenAIRequestProvider, OpenAIResult, Usage};
+use crate::{endpoints::OpenAIRequestProvider, OpenAIError, OpenAIResult, Usage};
 
-use super::{standard::ChatCompletion, FinishReason, UnifiedChatCompletionResponseMessage};
+use super::{
+    response_accessors, standard::ChatCompletion, ChoiceLogprobs, CompletionResponse,
+    ContentFilterResults, FinishReason, Message, Model, Role,
+};
 
 /// A chat completion request who's response conforms to a particular JSON schema.
 ///
 /// All types which are structured must derive `kind_openai::OpenAISchema`, as well as
 /// `serde::Deserialize`. Take a look at the docs of that trait for a better idea of how
 /// to use it.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(bound(deserialize = "S: OpenAISchema"))]
 pub struct StructuredChatCompletion<'a, S> {
-    #[serde(flatten)]
+    #[serde(flatten, borrow)]
     pub(super) base_request: ChatCompletion<'a>,
+    // the schema is always regenerated from `S` when deserializing, so that a stored request can't
+    // drift from the type that its response is deserialized into.
+    #[serde(
+        skip_deserializing,
+        default = "ChatCompletionRequestResponseFormat::of::<S>"
+    )]
     pub(super) response_format: ChatCompletionRequestResponseFormat,
     // tether the schema type to the request so that drifting between the request and response
     // type when deserialization time comes is impossible
@@ -21,12 +31,82 @@ pub struct StructuredChatCompletion<'a, S> {
     pub(super) _phantom: std::marker::PhantomData<S>,
 }
 
+// derived `Clone` would require `S: Clone`, which isn't necessary since `S` is never actually stored.
+impl<S> Clone for StructuredChatCompletion<'_, S> {
+    fn clone(&self) -> Self {
+        Self {
+            base_request: self.base_request.clone(),
+            response_format: self.response_format.clone(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, S> StructuredChatCompletion<'a, S> {
+    /// Replaces the messages, keeping the schema and the rest of the configuration. Useful for building a
+    /// request once and firing (a clone of) it repeatedly with different inputs.
+    pub fn with_messages(self, messages: Vec<Message<'a>>) -> Self {
+        Self {
+            base_request: self.base_request.with_messages(messages),
+            ..self
+        }
+    }
+
+    /// Replaces the model, keeping the schema, messages, and the rest of the configuration. Useful for A/B
+    /// testing the same prompt across models.
+    pub fn with_model(self, model: Model) -> Self {
+        Self {
+            base_request: self.base_request.with_model(model),
+            ..self
+        }
+    }
+
+    /// Replaces the extra fields merged into the request body, keeping the schema and the rest of the
+    /// configuration. Like on `ChatCompletion`, they aren't part of the serialized form, so a deserialized
+    /// request needs them set again.
+    pub fn with_extra_body(self, extra_body: serde_json::Value) -> Self {
+        Self {
+            base_request: self.base_request.with_extra_body(extra_body),
+            ..self
+        }
+    }
+}
+
 /// Enum that serializes itself into the part of the request body where OpenAI expects the schema.
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 // TODO: fix this so that `content = "json_schema"` is not necessary
 #[serde(tag = "type", content = "json_schema", rename_all = "snake_case")]
 pub(super) enum ChatCompletionRequestResponseFormat {
-    JsonSchema(GeneratedOpenAISchema),
+    JsonSchema(ResponseFormatSchema),
+}
+
+/// The schema sent as the response format.
+#[derive(Serialize, Clone)]
+#[serde(untagged)]
+pub(super) enum ResponseFormatSchema {
+    /// The schema exactly as the derive generated it.
+    Derived(GeneratedOpenAISchema),
+    /// A derived schema whose name or description was replaced for this request, which is owned so that
+    /// it's dropped with the request.
+    Overridden(serde_json::Value),
+}
+
+impl ChatCompletionRequestResponseFormat {
+    pub(super) fn of<S: OpenAISchema>() -> Self {
+        Self::JsonSchema(ResponseFormatSchema::Derived(S::openai_schema()))
+    }
+
+    pub(super) fn named<S: OpenAISchema>(name: &str, description: Option<&str>) -> Self {
+        Self::JsonSchema(ResponseFormatSchema::Overridden(
+            S::openai_schema().with_name_and_description(name, description),
+        ))
+    }
+
+    pub(super) fn described<S: OpenAISchema>(description: &str) -> Self {
+        Self::JsonSchema(ResponseFormatSchema::Overridden(
+            S::openai_schema().with_description(description),
+        ))
+    }
 }
 
 impl<S> OpenAIRequestProvider for StructuredChatCompletion<'_, S>
@@ -37,45 +117,175 @@ where
 
     const METHOD: reqwest::Method = reqwest::Method::POST;
 
-    fn path_with_leading_slash() -> String {
+    fn path_with_leading_slash(&self) -> String {
         "/chat/completions".to_string()
     }
+
+    fn validate(&self) -> OpenAIResult<()> {
+        self.base_request.validate()
+    }
+
+    fn extra_body(&self) -> Option<&serde_json::Value> {
+        self.base_request.extra_body()
+    }
 }
 
 impl<S> super::super::private::Sealed for StructuredChatCompletion<'_, S> {}
 
 /// A response from a structured chat completion request.
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 #[serde(bound(deserialize = "S: DeserializeOwned"))]
 pub struct StructuredChatCompletionResponse<S> {
+    // OpenAI always sends these, but some OpenAI-compatible providers leave them out, which shouldn't make
+    // the whole response fail to deserialize.
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    created: i64,
+    #[serde(default)]
+    model: String,
+    system_fingerprint: Option<String>,
     choices: Vec<StructuredChatCompletionResponseChoice<S>>,
-    usage: Usage,
+    // some proxies (such as LiteLLM and Azure) occasionally leave out the usage block, which shouldn't make
+    // the whole response fail to deserialize.
+    usage: Option<Usage>,
 }
 
 impl<S> StructuredChatCompletionResponse<S> {
+    response_accessors!();
+
     /// Takes the first message in the response consumes the response.
     pub fn take_first_choice(self) -> Option<StructuredChatCompletionResponseChoice<S>> {
-        self.choices.into_iter().next()
+        self.into_iter().next()
+    }
+
+    /// Consumes the response and gives the parsed value of the first choice alongside the usage, which
+    /// is otherwise awkward to hold on to once the choice has been taken. The usage is `None` if the
+    /// provider didn't report it.
+    pub fn into_first_parsed(self) -> OpenAIResult<(S, Option<Usage>)> {
+        let usage = self.usage;
+        let value = self
+            .take_first_choice()
+            .ok_or_else(super::no_choices_error)?
+            .message()?;
+        Ok((value, usage))
+    }
+
+    pub fn choices(&self) -> &[StructuredChatCompletionResponseChoice<S>] {
+        &self.choices
+    }
+
+    /// Consumes the response and gives every choice, for when `n` was greater than one.
+    pub fn take_choices(self) -> Vec<StructuredChatCompletionResponseChoice<S>> {
+        self.into_iter().collect()
+    }
+}
+
+impl<S> CompletionResponse for StructuredChatCompletionResponse<S> {
+    fn finish_reason(&self) -> Option<FinishReason> {
+        self.choices.first().map(|choice| choice.finish_reason)
     }
 
-    /// Gives the usage tokens of the response.
-    pub fn usage(&self) -> Usage {
+    fn refusal(&self) -> Option<&str> {
+        match &self.choices.first()?.message {
+            StructuredChatCompletionResponseMessage::Refusal(refusal) => Some(refusal),
+            _ => None,
+        }
+    }
+
+    fn usage(&self) -> Option<Usage> {
         self.usage
     }
 }
 
-#[derive(Deserialize)]
-#[serde(bound(deserialize = "S: DeserializeOwned"))]
+impl<S> IntoIterator for StructuredChatCompletionResponse<S> {
+    type Item = StructuredChatCompletionResponseChoice<S>;
+    type IntoIter = std::vec::IntoIter<StructuredChatCompletionResponseChoice<S>>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        for choice in &mut self.choices {
+            choice.usage = self.usage;
+        }
+        self.choices.into_iter()
+    }
+}
+
+impl<'a, S> IntoIterator for &'a StructuredChatCompletionResponse<S> {
+    type Item = &'a StructuredChatCompletionResponseChoice<S>;
+    type IntoIter = std::slice::Iter<'a, StructuredChatCompletionResponseChoice<S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.iter()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(
+    bound(deserialize = "S: DeserializeOwned"),
+    try_from = "RawStructuredChatCompletionResponseChoice"
+)]
 pub struct StructuredChatCompletionResponseChoice<S> {
     finish_reason: FinishReason,
     index: i32,
     message: StructuredChatCompletionResponseMessage<S>,
+    logprobs: Option<ChoiceLogprobs>,
+    content_filter_results: Option<ContentFilterResults>,
+    // copied over from the response when the choice is taken out of it, so that refusals can report it
+    usage: Option<Usage>,
 }
 
 impl<S> StructuredChatCompletionResponseChoice<S> {
     /// Returns your desired type that was produced from OpenAI.
+    ///
+    /// If the model ran out of tokens before finishing the JSON, this gives
+    /// `OpenAIError::TruncatedStructuredOutput` rather than a deserialization error.
     pub fn message(self) -> OpenAIResult<S> {
-        Into::<UnifiedChatCompletionResponseMessage<S>>::into(self.message).into()
+        self.message_detailed().map_err(Into::into)
+    }
+
+    /// Like `message`, but a refusal keeps the finish reason and usage alongside the refusal text,
+    /// which is handy for logging refused (but still billed) requests. Truncated output is reported
+    /// the same way, see `RefusalDetails::truncated_output`.
+    pub fn message_detailed(self) -> Result<S, RefusalDetails> {
+        let outcome = match self.message {
+            StructuredChatCompletionResponseMessage::Content { value, .. } => return Ok(value),
+            StructuredChatCompletionResponseMessage::Refusal(refusal) => {
+                NoValueOutcome::Refused(refusal)
+            }
+            StructuredChatCompletionResponseMessage::Truncated(raw) => {
+                NoValueOutcome::Truncated(raw)
+            }
+        };
+
+        Err(RefusalDetails {
+            outcome,
+            finish_reason: self.finish_reason,
+            usage: self.usage,
+        })
+    }
+
+    /// Turns the choice into an assistant message carrying the raw JSON the model produced (or its refusal),
+    /// so that it can be appended to the history of the next request.
+    pub fn into_message(self) -> Message<'static> {
+        let message = Message::role(Role::Assistant);
+        match self.message {
+            StructuredChatCompletionResponseMessage::Content { raw, .. }
+            | StructuredChatCompletionResponseMessage::Truncated(raw) => {
+                message.content(raw.into()).build()
+            }
+            StructuredChatCompletionResponseMessage::Refusal(refusal) => {
+                message.refusal(refusal.into()).build()
+            }
+        }
+    }
+
+    /// The unparsed JSON that the model produced, exactly as it was received. Empty if the model refused.
+    pub fn raw_content(&self) -> &str {
+        match &self.message {
+            StructuredChatCompletionResponseMessage::Content { raw, .. }
+            | StructuredChatCompletionResponseMessage::Truncated(raw) => raw,
+            StructuredChatCompletionResponseMessage::Refusal(_) => "",
+        }
     }
 
     pub fn finish_reason(&self) -> FinishReason {
@@ -85,33 +295,140 @@ impl<S> StructuredChatCompletionResponseChoice<S> {
     pub fn index(&self) -> i32 {
         self.index
     }
+
+    /// The log probabilities of the generated tokens, if `logprobs` was enabled on the request.
+    pub fn logprobs(&self) -> Option<&ChoiceLogprobs> {
+        self.logprobs.as_ref()
+    }
+
+    /// The results of the content filters run over the choice, for providers that report them (such as Azure).
+    pub fn content_filter_results(&self) -> Option<&ContentFilterResults> {
+        self.content_filter_results.as_ref()
+    }
+
+    /// The mean log probability of the generated tokens, which can be used as a rough confidence score
+    /// for the extraction. Only present if `logprobs` was enabled on the request.
+    pub fn avg_logprob(&self) -> Option<f64> {
+        self.logprobs.as_ref()?.average_content_logprob()
+    }
+}
+
+// leave private, messages should only be interacted with through the choice.
+#[derive(Clone, Debug)]
+enum StructuredChatCompletionResponseMessage<S> {
+    Content {
+        value: S,
+        // the exact JSON the model produced, kept around so it can be logged or stored verbatim.
+        raw: String,
+    },
+    // refused messages have no content, so there's nothing to parse into `S`
+    Refusal(String),
+    // the model ran out of tokens partway through the JSON, which is never going to parse
+    Truncated(String),
 }
 
-// leave private, messages should only be interacted with through the unified message type.
+// the choice is deserialized in two steps, since whether or not the content can be parsed into `S`
+// depends on why the model stopped generating.
 #[derive(Deserialize)]
-#[serde(bound(deserialize = "S: DeserializeOwned"))]
-struct StructuredChatCompletionResponseMessage<S> {
-    #[serde(deserialize_with = "de_from_str")]
-    content: S,
+struct RawStructuredChatCompletionResponseChoice {
+    finish_reason: FinishReason,
+    index: i32,
+    message: RawStructuredChatCompletionResponseMessage,
+    logprobs: Option<ChoiceLogprobs>,
+    content_filter_results: Option<ContentFilterResults>,
+}
+
+#[derive(Deserialize)]
+struct RawStructuredChatCompletionResponseMessage {
+    content: Option<String>,
     refusal: Option<String>,
 }
 
-fn de_from_str<'de, D, S>(deserializer: D) -> Result<S, D::Error>
+impl<S> TryFrom<RawStructuredChatCompletionResponseChoice>
+    for StructuredChatCompletionResponseChoice<S>
 where
-    D: Deserializer<'de>,
     S: DeserializeOwned,
 {
-    let s = String::deserialize(deserializer)?;
-    serde_json::from_str(&s).map_err(serde::de::Error::custom)
+    type Error = serde_json::Error;
+
+    fn try_from(value: RawStructuredChatCompletionResponseChoice) -> Result<Self, Self::Error> {
+        let RawStructuredChatCompletionResponseMessage { content, refusal } = value.message;
+
+        let message = match (refusal, content) {
+            (Some(refusal), _) => StructuredChatCompletionResponseMessage::Refusal(refusal),
+            (None, Some(raw)) if matches!(value.finish_reason, FinishReason::Length) => {
+                StructuredChatCompletionResponseMessage::Truncated(raw)
+            }
+            (None, Some(raw)) => StructuredChatCompletionResponseMessage::Content {
+                value: serde_json::from_str(&raw)?,
+                raw,
+            },
+            (None, None) => {
+                return Err(<serde_json::Error as serde::de::Error>::custom(
+                    "the message has neither content nor a refusal",
+                ))
+            }
+        };
+
+        Ok(Self {
+            finish_reason: value.finish_reason,
+            index: value.index,
+            message,
+            logprobs: value.logprobs,
+            content_filter_results: value.content_filter_results,
+            usage: None,
+        })
+    }
 }
 
-impl<S> From<StructuredChatCompletionResponseMessage<S>>
-    for UnifiedChatCompletionResponseMessage<S>
-{
-    fn from(value: StructuredChatCompletionResponseMessage<S>) -> Self {
-        UnifiedChatCompletionResponseMessage {
-            content: value.content,
-            refusal: value.refusal,
+/// The details of a structured completion that didn't produce a value, either because the model refused
+/// or because it ran out of tokens partway through the JSON. The request was still billed, so the usage
+/// is kept around for logging.
+#[derive(Debug, Clone)]
+pub struct RefusalDetails {
+    outcome: NoValueOutcome,
+    finish_reason: FinishReason,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone)]
+enum NoValueOutcome {
+    Refused(String),
+    Truncated(String),
+}
+
+impl RefusalDetails {
+    /// The model's explanation of why it refused. Empty if the output was truncated instead.
+    pub fn refusal(&self) -> &str {
+        match &self.outcome {
+            NoValueOutcome::Refused(refusal) => refusal,
+            NoValueOutcome::Truncated(_) => "",
+        }
+    }
+
+    /// The partial JSON that the model produced before it ran out of tokens, if that's why there's no value.
+    pub fn truncated_output(&self) -> Option<&str> {
+        match &self.outcome {
+            NoValueOutcome::Truncated(raw) => Some(raw),
+            NoValueOutcome::Refused(_) => None,
+        }
+    }
+
+    pub fn finish_reason(&self) -> FinishReason {
+        self.finish_reason
+    }
+
+    /// The usage tokens of the response the choice came from, if the provider reported them.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+impl From<RefusalDetails> for OpenAIError {
+    fn from(value: RefusalDetails) -> Self {
+        match value.outcome {
+            NoValueOutcome::Refused(refusal) => OpenAIError::Refusal(refusal),
+            NoValueOutcome::Truncated(raw) => OpenAIError::TruncatedStructuredOutput(raw),
         }
     }
 }
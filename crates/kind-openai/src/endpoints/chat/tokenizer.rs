@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use super::{ChatCompletion, Message, Model, Role, StructuredChatCompletion};
+
+// every message is wrapped in a few special tokens, and the reply is primed with a few more. these match
+// OpenAI's own accounting for gpt-4o.
+const TOKENS_PER_MESSAGE: usize = 3;
+const TOKENS_PER_NAME: usize = 1;
+const TOKENS_PER_REPLY: usize = 3;
+
+impl Model {
+    fn bpe(&self) -> &'static tiktoken_rs::CoreBPE {
+        match self {
+            Model::Gpt4o_2024_11_20
+            | Model::Gpt4o_2024_08_06
+            | Model::Gpt4o
+            | Model::Gpt4oMini
+            | Model::Gpt4oAudioPreview => tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+
+    /// The IDs of the tokens that the model's tokenizer splits the text into.
+    pub fn token_ids(&self, text: &str) -> Vec<i32> {
+        self.bpe()
+            .encode_ordinary(text)
+            .into_iter()
+            .map(|token| token as i32)
+            .collect()
+    }
+
+    /// The number of prompt tokens the messages are billed as, counting the tokens that wrap each message.
+    /// Images and tool calls aren't counted.
+    pub fn count_message_tokens(&self, messages: &[Message<'_>]) -> usize {
+        let bpe = self.bpe();
+        let count = |text: &str| bpe.encode_ordinary(text).len();
+
+        messages
+            .iter()
+            .map(|message| {
+                TOKENS_PER_MESSAGE
+                    + count(role_name(message.role))
+                    + message
+                        .content
+                        .as_ref()
+                        .map_or(0, |content| count(&content.text()))
+                    + message
+                        .name
+                        .as_deref()
+                        .map_or(0, |name| TOKENS_PER_NAME + count(name))
+            })
+            .sum::<usize>()
+            + TOKENS_PER_REPLY
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Developer => "developer",
+        Role::Tool => "tool",
+    }
+}
+
+/// A request whose prompt can be tokenized locally, to count its tokens without sending it.
+pub trait CountTokens {
+    /// The number of prompt tokens the request's messages are billed as, under the request's model.
+    fn count_prompt_tokens(&self) -> usize;
+}
+
+impl CountTokens for ChatCompletion<'_> {
+    fn count_prompt_tokens(&self) -> usize {
+        self.chat_model().count_message_tokens(self.messages())
+    }
+}
+
+// the schema is also part of the prompt, but how OpenAI renders it isn't documented, so only the
+// messages are counted.
+impl<S> CountTokens for StructuredChatCompletion<'_, S> {
+    fn count_prompt_tokens(&self) -> usize {
+        self.base_request.count_prompt_tokens()
+    }
+}
+
+/// Builds a `logit_bias` map from words rather than raw token IDs, for example
+/// `logit_bias_for_words(Model::Gpt4o, &[("Jonathan", -100)])` to discourage the model from saying "Jonathan".
+///
+/// Words are tokenized both on their own and with a leading space, since that's how they appear mid-sentence.
+/// Note that if a word is split into several tokens, every one of them is biased, which also affects other
+/// words that share those tokens.
+pub fn logit_bias_for_words(model: Model, words: &[(&str, i32)]) -> HashMap<i32, i32> {
+    let mut logit_bias = HashMap::new();
+    for (word, bias) in words {
+        for token in model
+            .token_ids(word)
+            .into_iter()
+            .chain(model.token_ids(&format!(" {word}")))
+        {
+            logit_bias.insert(token, *bias);
+        }
+    }
+
+    logit_bias
+}
+
+// converts a bias of any integer type for `logit_bias_str!`, without wrapping the biases that don't fit into
+// something in range. those are still out of range once saturated, so building the request rejects them.
+#[doc(hidden)]
+pub fn saturating_bias<B>(bias: B) -> i32
+where
+    B: TryInto<i32> + PartialOrd + Default + Copy,
+{
+    bias.try_into().unwrap_or(if bias < B::default() {
+        i32::MIN
+    } else {
+        i32::MAX
+    })
+}
+
+/// Builds a `logit_bias` map from words rather than raw token IDs, like so:
+/// `logit_bias_str!("Jonathan" => -100, "Jon" => -50)`
+///
+/// The words are tokenized with gpt-4o's tokenizer, which every non-reasoning chat model currently shares.
+/// A model can be given first to use its tokenizer instead: `logit_bias_str!(Model::Gpt4oMini; "Jonathan" => -100)`.
+/// See `logit_bias_for_words` for how words are split into tokens.
+#[macro_export]
+macro_rules! logit_bias_str {
+    ($model:expr; $($word:expr => $bias:expr),* $(,)?) => {
+        ::kind_openai::endpoints::chat::logit_bias_for_words(
+            $model,
+            &[$((
+                ::std::convert::AsRef::<str>::as_ref(&$word),
+                ::kind_openai::endpoints::chat::saturating_bias($bias),
+            )),*],
+        )
+    };
+
+    ($($word:expr => $bias:expr),* $(,)?) => {
+        ::kind_openai::logit_bias_str!(::kind_openai::endpoints::chat::Model::Gpt4o; $($word => $bias),*)
+    };
+}
@@ -0,0 +1,102 @@
+use bon::Builder;
+use kind_openai_schema::{GeneratedOpenAISchema, OpenAISchema};
+use serde::{Deserialize, Serialize};
+
+/// A function that the model may call, which is provided to the request with `tools`.
+///
+/// Construct with `Tool::function`, like so:
+/// `Tool::function("get_weather").parameters(ToolParameters::of::<Weather>()).build()`
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
+#[serde(into = "RawTool", from = "RawTool")]
+#[builder(start_fn = function)]
+pub struct Tool {
+    /// The name the model calls the function by, which may only contain letters, digits, underscores, and dashes.
+    #[builder(start_fn, into)]
+    name: String,
+    /// What the function does, which the model uses to decide when to call it. Defaults to the docstring of
+    /// derived parameters.
+    #[builder(into)]
+    description: Option<String>,
+    parameters: ToolParameters,
+    /// Whether the arguments must exactly follow the parameters schema. Defaults to whatever derived
+    /// parameters were generated with, and to unset for raw parameters.
+    strict: Option<bool>,
+}
+
+/// The schema of a tool's arguments, either derived from a type or built by hand.
+#[derive(Debug, Clone)]
+pub enum ToolParameters {
+    /// The schema of a type that derives `OpenAISchema`, whose arguments can then be parsed into that type.
+    Derived(GeneratedOpenAISchema),
+    /// A JSON schema built at runtime, for tools that aren't known at compile time (such as those loaded from
+    /// plugins or configuration).
+    Raw(serde_json::Value),
+}
+
+impl ToolParameters {
+    /// The parameters of a tool whose arguments deserialize into `S`.
+    pub fn of<S: OpenAISchema>() -> Self {
+        Self::Derived(S::openai_schema())
+    }
+}
+
+impl From<serde_json::Value> for ToolParameters {
+    fn from(schema: serde_json::Value) -> Self {
+        Self::Raw(schema)
+    }
+}
+
+// the shape OpenAI expects. derived schemas are unwrapped here, since they're generated in the shape of a
+// response format rather than a bare JSON schema. tools that are deserialized always come back raw.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RawTool {
+    Function { function: RawFunction },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawFunction {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    parameters: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strict: Option<bool>,
+}
+
+impl From<Tool> for RawTool {
+    fn from(tool: Tool) -> Self {
+        let (parameters, description, strict) = match tool.parameters {
+            ToolParameters::Derived(schema) => {
+                let mut schema = schema.to_value();
+                (
+                    schema["schema"].take(),
+                    tool.description
+                        .or_else(|| schema["description"].as_str().map(str::to_owned)),
+                    tool.strict.or(schema["strict"].as_bool()),
+                )
+            }
+            ToolParameters::Raw(parameters) => (parameters, tool.description, tool.strict),
+        };
+
+        RawTool::Function {
+            function: RawFunction {
+                name: tool.name,
+                description,
+                parameters,
+                strict,
+            },
+        }
+    }
+}
+
+impl From<RawTool> for Tool {
+    fn from(RawTool::Function { function }: RawTool) -> Self {
+        Tool {
+            name: function.name,
+            description: function.description,
+            parameters: ToolParameters::Raw(function.parameters),
+            strict: function.strict,
+        }
+    }
+}
@@ -1,19 +1,40 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+};
 
-use bon::{builder, Builder};
+use bon::Builder;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-use super::OpenAIRequestProvider;
+use crate::{OpenAIResult, Usage};
+
+use super::{
+    chat::{CompletionResponse, FinishReason, UnifiedChatCompletionResponseMessage},
+    stream::EventStream,
+    OpenAIRequestProvider, OpenAIStreamingRequestProvider,
+};
 
 /// The model to use to create a chat reasoning completion.
-#[derive(Serialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 #[allow(non_camel_case_types)]
 pub enum ReasoningModel {
+    #[deprecated(
+        since = "0.3.10",
+        note = "o1-preview has been retired by OpenAI, use `ReasoningModel::O1` instead"
+    )]
     #[serde(rename = "o1-preview")]
     O1Preview,
+    #[deprecated(
+        since = "0.3.10",
+        note = "o1-mini has been retired by OpenAI, use `ReasoningModel::O1` instead"
+    )]
     #[serde(rename = "o1-mini")]
     O1Mini,
+    #[deprecated(
+        since = "0.3.10",
+        note = "o1-mini has been retired by OpenAI, use `ReasoningModel::O1_2024_12_17` instead"
+    )]
     #[serde(rename = "o1-mini-2024-09-12")]
     O1Mini_2024_09_12,
     #[serde(rename = "o1")]
@@ -36,7 +57,7 @@ pub enum Role {
 
 /// The amount of effort the model puts into the reasoning. This is essentially the length of the reasoning tokens.
 /// Default is medium.
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum ReasoningEffort {
     Low,
@@ -45,7 +66,7 @@ pub enum ReasoningEffort {
 }
 
 /// A chat reasoning completion request. This currently does not support structured outputs.
-#[derive(Serialize, Debug, Clone, Builder)]
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
 #[builder(start_fn = model, state_mod(vis = "pub"))]
 pub struct ChatReasoningCompletion<'a> {
     #[builder(start_fn)]
@@ -54,6 +75,11 @@ pub struct ChatReasoningCompletion<'a> {
     store: Option<bool>,
     metadata: Option<HashMap<String, String>>,
     reasoning_effort: Option<ReasoningEffort>,
+    /// A stable identifier for the end user the request is made on behalf of, which OpenAI uses to detect
+    /// abuse and attribute usage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(into)]
+    user: Option<Cow<'a, str>>,
 }
 
 impl OpenAIRequestProvider for ChatReasoningCompletion<'_> {
@@ -61,7 +87,7 @@ impl OpenAIRequestProvider for ChatReasoningCompletion<'_> {
 
     const METHOD: Method = Method::POST;
 
-    fn path_with_leading_slash() -> String {
+    fn path_with_leading_slash(&self) -> String {
         "/chat/completions".to_string()
     }
 }
@@ -69,7 +95,7 @@ impl OpenAIRequestProvider for ChatReasoningCompletion<'_> {
 impl super::private::Sealed for ChatReasoningCompletion<'_> {}
 
 /// A chat reasoning completion message. This currently does not support structured outputs.
-#[derive(Serialize, Debug, Clone, Builder)]
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
 #[builder(start_fn = role)]
 pub struct ReasoningMessage<'a> {
     #[builder(start_fn)]
@@ -110,29 +136,206 @@ macro_rules! reasoning_assistant_message {
     };
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct ChatReasoningCompletionResponse {
+    // OpenAI always sends these, but some OpenAI-compatible providers leave them out, which shouldn't make
+    // the whole response fail to deserialize.
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    created: i64,
+    #[serde(default)]
+    model: String,
     choices: Vec<ChatReasoningCompletionResponseChoice>,
+    usage: Option<Usage>,
 }
 
 impl ChatReasoningCompletionResponse {
+    /// The ID of the completion, which can be used to retrieve it later if it was created with `store`.
+    /// Empty if the provider didn't report it.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The unix timestamp (in seconds) of when the completion was created, or zero if the provider didn't
+    /// report it.
+    pub fn created(&self) -> i64 {
+        self.created
+    }
+
+    /// The exact model that generated the completion, for example `o1-2024-12-17` when `o1` was requested.
+    /// Empty if the provider didn't report it.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     pub fn take_first_choice(self) -> Option<ChatReasoningCompletionResponseChoice> {
         self.choices.into_iter().next()
     }
+
+    pub fn choices(&self) -> &[ChatReasoningCompletionResponseChoice] {
+        &self.choices
+    }
+
+    /// Gives the usage tokens of the response, including the reasoning tokens, if the provider reported them.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
 }
 
-#[derive(Deserialize)]
+impl CompletionResponse for ChatReasoningCompletionResponse {
+    fn finish_reason(&self) -> Option<FinishReason> {
+        self.choices.first().map(|choice| choice.finish_reason)
+    }
+
+    fn refusal(&self) -> Option<&str> {
+        self.choices.first()?.refusal()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+impl IntoIterator for ChatReasoningCompletionResponse {
+    type Item = ChatReasoningCompletionResponseChoice;
+    type IntoIter = std::vec::IntoIter<ChatReasoningCompletionResponseChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ChatReasoningCompletionResponse {
+    type Item = &'a ChatReasoningCompletionResponseChoice;
+    type IntoIter = std::slice::Iter<'a, ChatReasoningCompletionResponseChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.iter()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct ChatReasoningCompletionResponseChoice {
+    finish_reason: FinishReason,
     message: ChatReasoningCompletionResponseMessage,
 }
 
 impl ChatReasoningCompletionResponseChoice {
-    pub fn message(self) -> String {
-        self.message.content
+    /// Takes the message and returns a result that may contain a refusal.
+    pub fn message(self) -> OpenAIResult<String> {
+        Into::<UnifiedChatCompletionResponseMessage<String>>::into(self.message).into()
+    }
+
+    pub fn finish_reason(&self) -> FinishReason {
+        self.finish_reason
+    }
+
+    /// The reason the model gave for refusing, if it did.
+    pub fn refusal(&self) -> Option<&str> {
+        self.message.refusal.as_deref()
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct ChatReasoningCompletionResponseMessage {
-    content: String,
+    // null when the model refused
+    content: Option<String>,
+    refusal: Option<String>,
+}
+
+impl From<ChatReasoningCompletionResponseMessage> for UnifiedChatCompletionResponseMessage<String> {
+    fn from(value: ChatReasoningCompletionResponseMessage) -> Self {
+        UnifiedChatCompletionResponseMessage {
+            content: value.content.unwrap_or_default(),
+            refusal: value.refusal,
+        }
+    }
+}
+
+/// A piece of a streamed reasoning completion, either part of the model's reasoning summary or part of
+/// its final answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReasoningCompletionDelta {
+    /// Text generated for the summary of the model's reasoning, which arrives before the answer. Only
+    /// some models and providers emit these.
+    Summary(String),
+    /// Text generated for the answer itself.
+    Content(String),
+}
+
+/// A streamed chat reasoning completion. Call `next` until it returns `None` to consume the stream.
+pub struct ChatReasoningCompletionStream {
+    events: EventStream<ChatReasoningCompletionChunk>,
+    // a single chunk can carry both a summary and a content delta, so they're queued up and handed out
+    // one at a time
+    pending: VecDeque<ReasoningCompletionDelta>,
+    usage: Option<Usage>,
+}
+
+impl ChatReasoningCompletionStream {
+    /// Waits for the next delta of the response, returning `None` once the stream has completed.
+    pub async fn next(&mut self) -> Option<OpenAIResult<ReasoningCompletionDelta>> {
+        loop {
+            if let Some(delta) = self.pending.pop_front() {
+                return Some(Ok(delta));
+            }
+
+            let chunk = match self.events.next().await? {
+                Ok(chunk) => chunk,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if chunk.usage.is_some() {
+                self.usage = chunk.usage;
+            }
+            for choice in chunk.choices {
+                let delta = choice.delta;
+                if let Some(summary) = delta.reasoning_summary.filter(|s| !s.is_empty()) {
+                    self.pending
+                        .push_back(ReasoningCompletionDelta::Summary(summary));
+                }
+                if let Some(content) = delta.content.filter(|c| !c.is_empty()) {
+                    self.pending
+                        .push_back(ReasoningCompletionDelta::Content(content));
+                }
+            }
+        }
+    }
+
+    /// Gives the usage tokens of the entire response, which is only available once the stream has completed.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+impl OpenAIStreamingRequestProvider for ChatReasoningCompletion<'_> {
+    type Stream = ChatReasoningCompletionStream;
+
+    fn stream_from_response(response: reqwest::Response) -> Self::Stream {
+        ChatReasoningCompletionStream {
+            events: EventStream::new(response),
+            pending: VecDeque::new(),
+            usage: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatReasoningCompletionChunk {
+    choices: Vec<ChatReasoningCompletionChunkChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct ChatReasoningCompletionChunkChoice {
+    delta: ChatReasoningCompletionChunkDelta,
+}
+
+#[derive(Deserialize)]
+struct ChatReasoningCompletionChunkDelta {
+    content: Option<String>,
+    // openai-compatible providers tend to call this `reasoning`
+    #[serde(alias = "reasoning")]
+    reasoning_summary: Option<String>,
 }
@@ -0,0 +1,125 @@
+use bon::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{OpenAIResult, Usage};
+
+use super::{
+    chat::FinishReason, sampling::sampling_setters, OpenAIRequestProvider, SamplingParams,
+};
+
+/// A request to the legacy completions endpoint, which continues a raw prompt rather than a
+/// conversation. Most use cases should prefer chat completions, but this is still needed for base
+/// models and fine-tunes of them.
+///
+/// Construct with `Completion::model`
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
+#[builder(start_fn = model)]
+pub struct Completion<'a> {
+    /// The name of the model to use, for example `gpt-3.5-turbo-instruct` or `davinci-002`.
+    #[builder(start_fn)]
+    model: &'a str,
+    #[serde(flatten)]
+    #[builder(field)]
+    sampling: SamplingParams,
+    prompt: &'a str,
+    /// The text that comes after the completion, for inserting text rather than appending it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    /// Up to 4 sequences where the model will stop generating further tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<&'a str>>,
+    /// A unique identifier representing your end-user, which helps OpenAI monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<&'a str>,
+}
+
+impl<S> CompletionBuilder<'_, S>
+where
+    S: completion_builder::State,
+{
+    sampling_setters!();
+}
+
+impl OpenAIRequestProvider for Completion<'_> {
+    type Response = CompletionResponse;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/completions".to_string()
+    }
+
+    fn validate(&self) -> OpenAIResult<()> {
+        self.sampling.validate()
+    }
+}
+
+impl super::private::Sealed for Completion<'_> {}
+
+/// A response from a legacy completion request.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CompletionResponse {
+    choices: Vec<CompletionChoice>,
+    // left out by some OpenAI-compatible providers, like the usage of chat completions
+    usage: Option<Usage>,
+}
+
+impl CompletionResponse {
+    /// Takes the first choice in the response and consumes the response.
+    pub fn take_first_choice(self) -> Option<CompletionChoice> {
+        self.choices.into_iter().next()
+    }
+
+    pub fn choices(&self) -> &[CompletionChoice] {
+        &self.choices
+    }
+
+    /// Gives the usage tokens of the response, if the usage was reported.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+}
+
+impl IntoIterator for CompletionResponse {
+    type Item = CompletionChoice;
+    type IntoIter = std::vec::IntoIter<CompletionChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CompletionResponse {
+    type Item = &'a CompletionChoice;
+    type IntoIter = std::slice::Iter<'a, CompletionChoice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.choices.iter()
+    }
+}
+
+/// A response choice from a legacy completion request.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CompletionChoice {
+    text: String,
+    index: i32,
+    finish_reason: FinishReason,
+}
+
+impl CompletionChoice {
+    /// Consumes the choice and gives the generated text.
+    pub fn text(self) -> String {
+        self.text
+    }
+
+    pub fn index(&self) -> i32 {
+        self.index
+    }
+
+    pub fn finish_reason(&self) -> FinishReason {
+        self.finish_reason
+    }
+}
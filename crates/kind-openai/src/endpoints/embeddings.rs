@@ -1,25 +1,43 @@
-use bon::Builder;
+use std::{borrow::Cow, sync::Mutex};
+
+use bon::{builder, Builder};
+use futures_util::{StreamExt, TryStreamExt};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-use super::OpenAIRequestProvider;
+use crate::{auth::AuthTokenProvider, OpenAI, OpenAIError, OpenAIResult, Usage};
+
+use super::{estimate_tokens, OpenAIRequestProvider};
 
 /// The model used to create text embeddings.
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EmbeddingsModel {
     #[serde(rename = "text-embedding-3-large")]
     TextEmbedding3Large,
+    #[serde(rename = "text-embedding-3-small")]
+    TextEmbedding3Small,
+    #[serde(rename = "text-embedding-ada-002")]
+    TextEmbeddingAda002,
 }
 
 /// A text embeddings creation request.
 ///
 /// Construct with `Embeddings::model`
-#[derive(Serialize, Debug, Clone, Builder)]
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
 #[builder(start_fn = model)]
 pub struct Embeddings<'a> {
     #[builder(start_fn)]
     model: EmbeddingsModel,
-    input: &'a str,
+    /// The text to embed, either borrowed or owned.
+    #[builder(into)]
+    input: Cow<'a, str>,
+    /// The number of dimensions the resulting embeddings should have.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
+    /// A unique identifier representing your end-user, which helps OpenAI monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(into)]
+    user: Option<Cow<'a, str>>,
 }
 
 impl OpenAIRequestProvider for Embeddings<'_> {
@@ -27,19 +45,222 @@ impl OpenAIRequestProvider for Embeddings<'_> {
 
     const METHOD: reqwest::Method = Method::POST;
 
-    fn path_with_leading_slash() -> String {
+    fn path_with_leading_slash(&self) -> String {
         "/embeddings".to_string()
     }
 }
 
 impl super::private::Sealed for Embeddings<'_> {}
 
-#[derive(Deserialize)]
+// a request for many embeddings at once, only used internally by `embed_all` to keep the public
+// `Embeddings` request simple.
+#[derive(Serialize)]
+struct BatchEmbeddings<'a> {
+    model: EmbeddingsModel,
+    input: &'a [String],
+}
+
+impl OpenAIRequestProvider for BatchEmbeddings<'_> {
+    type Response = EmbeddingsResponse;
+
+    const METHOD: reqwest::Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/embeddings".to_string()
+    }
+}
+
+impl super::private::Sealed for BatchEmbeddings<'_> {}
+
+/// Embeds every input, splitting them into batches that respect OpenAI's per-request limits and sending
+/// up to `concurrency` batches at once. The embeddings are returned in the same order as the inputs,
+/// alongside which inputs (if any) were truncated by `max_input_tokens`.
+///
+/// Call like so: `embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs).call().await`
+#[builder]
+pub async fn embed_all<Auth, I>(
+    #[builder(start_fn)] client: &OpenAI<Auth>,
+    #[builder(start_fn)] model: EmbeddingsModel,
+    #[builder(start_fn)] inputs: I,
+    /// The maximum number of inputs sent in a single request. OpenAI allows at most 2048.
+    #[builder(default = 2048)]
+    batch_size: usize,
+    /// The maximum number of (roughly estimated) tokens sent in a single request. OpenAI allows at most 300,000.
+    #[builder(default = 300_000)]
+    max_batch_tokens: usize,
+    /// The maximum number of requests that are in flight at once.
+    #[builder(default = 4)]
+    concurrency: usize,
+    /// Opts into truncating inputs that are estimated to be over this many tokens, rather than letting a
+    /// single oversized input fail its whole batch. The inputs that were cut short are reported by
+    /// `EmbedAllOutput::truncated`.
+    max_input_tokens: Option<usize>,
+    /// Called each time a batch completes, with the progress so far, such as for showing a progress bar
+    /// and the running cost. Batches may complete out of order.
+    on_progress: Option<&(dyn Fn(EmbedProgress) + Send + Sync)>,
+) -> OpenAIResult<EmbedAllOutput>
+where
+    Auth: AuthTokenProvider,
+    I: IntoIterator<Item = String>,
+{
+    let mut truncated = Vec::new();
+    let inputs = inputs.into_iter().enumerate().map(|(index, mut input)| {
+        if let Some(max_input_tokens) = max_input_tokens {
+            if truncate_input(&mut input, max_input_tokens) {
+                truncated.push(index);
+            }
+        }
+        input
+    });
+    let batches = batch_inputs(inputs, batch_size.max(1), max_batch_tokens);
+    let progress = Mutex::new(EmbedProgress {
+        completed_batches: 0,
+        total_batches: batches.len(),
+        embedded_inputs: 0,
+        total_inputs: batches.iter().map(Vec::len).sum(),
+        usage: Usage::default(),
+    });
+
+    let embeddings = futures_util::stream::iter(batches)
+        .map(|batch| {
+            let progress = &progress;
+            async move {
+                let response = client
+                    .req(&BatchEmbeddings {
+                        model,
+                        input: &batch,
+                    })
+                    .await?;
+                let usage = response.usage();
+                let embeddings = response.embeddings();
+                // the batches are stitched back together by position, so one short batch would shift every
+                // embedding after it onto the wrong input
+                if embeddings.len() != batch.len() {
+                    return Err(OpenAIError::EmbeddingCountMismatch {
+                        expected: batch.len(),
+                        actual: embeddings.len(),
+                    });
+                }
+
+                if let Some(on_progress) = on_progress {
+                    let mut progress = progress
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    progress.completed_batches += 1;
+                    progress.embedded_inputs += batch.len();
+                    progress.usage += usage.unwrap_or_default();
+                    on_progress(*progress);
+                }
+
+                Ok(embeddings)
+            }
+        })
+        // `buffered` (as opposed to `buffer_unordered`) is what keeps the batches in their original order
+        .buffered(concurrency.max(1))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(EmbedAllOutput {
+        embeddings: embeddings.into_iter().flatten().collect(),
+        truncated,
+    })
+}
+
+/// The embeddings produced by `embed_all`.
+#[derive(Debug, Clone)]
+pub struct EmbedAllOutput {
+    embeddings: Vec<Vec<f32>>,
+    truncated: Vec<usize>,
+}
+
+impl EmbedAllOutput {
+    /// The embeddings, in the same order as the inputs.
+    pub fn embeddings(&self) -> &[Vec<f32>] {
+        &self.embeddings
+    }
+
+    /// Consumes the output and gives the embeddings, in the same order as the inputs.
+    pub fn into_embeddings(self) -> Vec<Vec<f32>> {
+        self.embeddings
+    }
+
+    /// The indices of the inputs that were truncated by `max_input_tokens` before being embedded, in
+    /// ascending order. Always empty if `max_input_tokens` wasn't set.
+    pub fn truncated(&self) -> &[usize] {
+        &self.truncated
+    }
+}
+
+/// How far along an `embed_all` call is, as reported to its `on_progress` callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedProgress {
+    pub completed_batches: usize,
+    pub total_batches: usize,
+    /// The number of inputs in the completed batches.
+    pub embedded_inputs: usize,
+    pub total_inputs: usize,
+    /// The usage of the completed batches, totalled.
+    pub usage: Usage,
+}
+
+/// Truncates the input so that it's estimated to be at most `max_tokens` tokens, returning whether or not
+/// anything was cut off. The estimate is rough (~4 characters per token), so leave some headroom below the
+/// model's actual limit.
+pub fn truncate_input(input: &mut String, max_tokens: usize) -> bool {
+    if estimate_tokens(input) <= max_tokens {
+        return false;
+    }
+
+    let mut len = max_tokens * 4;
+    while !input.is_char_boundary(len) {
+        len -= 1;
+    }
+    input.truncate(len);
+
+    true
+}
+
+fn batch_inputs(
+    inputs: impl IntoIterator<Item = String>,
+    batch_size: usize,
+    max_batch_tokens: usize,
+) -> Vec<Vec<String>> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut batch_tokens = 0;
+
+    for input in inputs {
+        let tokens = estimate_tokens(&input);
+        if !batch.is_empty()
+            && (batch.len() == batch_size || batch_tokens + tokens > max_batch_tokens)
+        {
+            batches.push(std::mem::take(&mut batch));
+            batch_tokens = 0;
+        }
+
+        batch_tokens += tokens;
+        batch.push(input);
+    }
+
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    batches
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct EmbeddingsResponse {
     data: Vec<EmbeddingsData>,
+    usage: Option<Usage>,
 }
 
 impl EmbeddingsResponse {
+    /// Gives the usage tokens of the response, if the provider reported them.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+
     /// Consumes the response and gives the embeddings.
     pub fn embedding(self) -> Vec<f32> {
         self.data
@@ -49,13 +270,152 @@ impl EmbeddingsResponse {
             .unwrap_or_default()
     }
 
+    /// Consumes the response and gives every embedding, in the same order as the inputs.
+    pub fn embeddings(mut self) -> Vec<Vec<f32>> {
+        self.data.sort_by_key(|d| d.index);
+        self.data.into_iter().map(|d| d.embedding).collect()
+    }
+
     /// Gives a reference to the generated embeddings.
     pub fn embedding_ref(&self) -> &[f32] {
         &self.data[0].embedding
     }
+
+    /// Consumes the response and gives the embeddings scaled to unit length. OpenAI's embeddings are
+    /// only approximately normalized, which isn't good enough for some ANN libraries.
+    pub fn embedding_normalized(mut self) -> Vec<f32> {
+        self.normalize();
+        self.embedding()
+    }
+
+    /// L2-normalizes the embeddings in place, so that subsequent calls to `embedding` and `embedding_ref`
+    /// give unit length vectors.
+    pub fn normalize(&mut self) {
+        for data in &mut self.data {
+            l2_normalize(&mut data.embedding);
+        }
+    }
 }
 
-#[derive(Deserialize)]
+fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    // leave zero vectors alone rather than filling them with NaNs
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
 struct EmbeddingsData {
+    #[serde(default)]
+    index: usize,
     embedding: Vec<f32>,
 }
+
+/// A minimal in-memory index of embeddings, searched by brute-force cosine similarity.
+///
+/// Every embedding must have the same number of dimensions, since comparing embeddings from different
+/// models (or different `dimensions` settings) silently gives meaningless similarities. Inserting or
+/// searching with an embedding of any other size gives `OpenAIError::DimensionMismatch`.
+///
+/// This is not a vector database. Every search is O(n) in the number of stored embeddings, which is
+/// perfectly fine for prototypes and small collections, but you'll want something smarter past a few
+/// hundred thousand entries.
+#[derive(Debug, Clone)]
+pub struct EmbeddingIndex<Id> {
+    // embeddings are normalized on insertion so that searching is just a dot product
+    entries: Vec<(Id, Vec<f32>)>,
+}
+
+impl<Id> Default for EmbeddingIndex<Id> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<Id> EmbeddingIndex<Id>
+where
+    Id: PartialEq + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an embedding to the index, replacing any embedding already stored under the same ID.
+    pub fn insert(&mut self, id: Id, mut embedding: Vec<f32>) -> OpenAIResult<()> {
+        self.check_dimensions(&embedding)?;
+        l2_normalize(&mut embedding);
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == id)
+        {
+            Some((_, existing)) => *existing = embedding,
+            None => self.entries.push((id, embedding)),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the embedding stored under the ID, returning whether or not there was one.
+    pub fn remove(&mut self, id: &Id) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|(existing, _)| existing != id);
+        self.entries.len() != len
+    }
+
+    /// Gives the IDs of the `top_k` embeddings most similar to the query, along with their cosine
+    /// similarity, from most to least similar.
+    pub fn search(&self, query: &[f32], top_k: usize) -> OpenAIResult<Vec<(Id, f32)>> {
+        self.check_dimensions(query)?;
+        let mut query = query.to_vec();
+        l2_normalize(&mut query);
+
+        let mut scored = self
+            .entries
+            .iter()
+            .map(|(id, embedding)| {
+                let similarity = embedding
+                    .iter()
+                    .zip(&query)
+                    .map(|(a, b)| a * b)
+                    .sum::<f32>();
+                (id, similarity)
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        Ok(scored
+            .into_iter()
+            .take(top_k)
+            .map(|(id, similarity)| (id.clone(), similarity))
+            .collect())
+    }
+
+    /// The number of dimensions every embedding in the index has, or `None` if it's empty.
+    pub fn dimensions(&self) -> Option<usize> {
+        self.entries.first().map(|(_, embedding)| embedding.len())
+    }
+
+    fn check_dimensions(&self, embedding: &[f32]) -> OpenAIResult<()> {
+        match self.dimensions() {
+            Some(dimensions) if dimensions != embedding.len() => {
+                Err(OpenAIError::DimensionMismatch {
+                    expected: dimensions,
+                    actual: embedding.len(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,220 @@
+use reqwest::{
+    multipart::{Form, Part},
+    Method,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{OpenAIRequestProvider, Page};
+
+/// The intended purpose of an uploaded file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum FilePurpose {
+    #[serde(rename = "assistants")]
+    Assistants,
+    #[serde(rename = "batch")]
+    Batch,
+    #[serde(rename = "fine-tune")]
+    FineTune,
+    #[serde(rename = "vision")]
+    Vision,
+    #[serde(rename = "user_data")]
+    UserData,
+    /// A purpose that this crate doesn't know about yet, as OpenAI names it. It's sent as is, so it can also
+    /// be used to upload or list files with a purpose that was added after this version.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl FilePurpose {
+    fn as_str(&self) -> &str {
+        match self {
+            FilePurpose::Assistants => "assistants",
+            FilePurpose::Batch => "batch",
+            FilePurpose::FineTune => "fine-tune",
+            FilePurpose::Vision => "vision",
+            FilePurpose::UserData => "user_data",
+            FilePurpose::Other(purpose) => purpose,
+        }
+    }
+}
+
+/// A request to upload a file, sent as a multipart form.
+#[derive(Serialize, Debug, Clone)]
+pub struct UploadFile {
+    #[serde(skip)]
+    bytes: Vec<u8>,
+    #[serde(skip)]
+    filename: String,
+    #[serde(skip)]
+    purpose: FilePurpose,
+}
+
+impl UploadFile {
+    /// Creates a request to upload the provided bytes under the provided filename.
+    pub fn new(
+        bytes: impl Into<Vec<u8>>,
+        filename: impl Into<String>,
+        purpose: FilePurpose,
+    ) -> Self {
+        Self {
+            bytes: bytes.into(),
+            filename: filename.into(),
+            purpose,
+        }
+    }
+}
+
+impl OpenAIRequestProvider for UploadFile {
+    type Response = FileObject;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/files".to_string()
+    }
+
+    fn multipart_form(&self) -> Option<Form> {
+        Some(
+            Form::new()
+                .text("purpose", self.purpose.as_str().to_owned())
+                .part(
+                    "file",
+                    Part::bytes(self.bytes.clone()).file_name(self.filename.clone()),
+                ),
+        )
+    }
+}
+
+impl super::private::Sealed for UploadFile {}
+
+/// A request to list the files that have been uploaded, optionally only those with a particular purpose.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ListFiles {
+    #[serde(skip)]
+    purpose: Option<FilePurpose>,
+}
+
+impl ListFiles {
+    /// Creates a request to list all uploaded files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists files uploaded with the provided purpose.
+    pub fn purpose(purpose: FilePurpose) -> Self {
+        Self {
+            purpose: Some(purpose),
+        }
+    }
+}
+
+impl OpenAIRequestProvider for ListFiles {
+    type Response = FileList;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/files".to_string()
+    }
+
+    fn query_params(&self) -> Vec<(String, String)> {
+        self.purpose
+            .as_ref()
+            .map(|purpose| ("purpose".to_owned(), purpose.as_str().to_owned()))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl super::private::Sealed for ListFiles {}
+
+/// A request to delete an uploaded file.
+#[derive(Serialize, Debug, Clone)]
+pub struct DeleteFile<'a> {
+    #[serde(skip)]
+    id: &'a str,
+}
+
+impl<'a> DeleteFile<'a> {
+    /// Creates a request to delete the file with the provided ID.
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
+    }
+}
+
+impl OpenAIRequestProvider for DeleteFile<'_> {
+    type Response = DeletedFile;
+
+    const METHOD: Method = Method::DELETE;
+
+    fn path_with_leading_slash(&self) -> String {
+        format!("/files/{}", self.id)
+    }
+}
+
+impl super::private::Sealed for DeleteFile<'_> {}
+
+/// An uploaded file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FileObject {
+    id: String,
+    bytes: u64,
+    created_at: i64,
+    filename: String,
+    purpose: FilePurpose,
+}
+
+impl FileObject {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The size of the file in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// The unix timestamp (in seconds) of when the file was uploaded.
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn purpose(&self) -> &FilePurpose {
+        &self.purpose
+    }
+}
+
+/// A list of uploaded files.
+pub type FileList = Page<FileObject>;
+
+impl Page<FileObject> {
+    pub fn files(&self) -> &[FileObject] {
+        self.data()
+    }
+
+    /// Consumes the list and gives the files.
+    pub fn into_files(self) -> Vec<FileObject> {
+        self.into_data()
+    }
+}
+
+/// The result of deleting a file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeletedFile {
+    id: String,
+    deleted: bool,
+}
+
+impl DeletedFile {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn deleted(&self) -> bool {
+        self.deleted
+    }
+}
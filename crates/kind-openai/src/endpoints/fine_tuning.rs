@@ -0,0 +1,156 @@
+use bon::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::OpenAIRequestProvider;
+
+/// A request to create a fine-tuning job from an uploaded training file.
+///
+/// Construct with `CreateFineTuningJob::model`
+#[derive(Serialize, Debug, Clone, Builder)]
+#[builder(start_fn = model)]
+pub struct CreateFineTuningJob<'a> {
+    /// The name of the model to fine-tune, for example `gpt-4o-mini-2024-07-18`.
+    #[builder(start_fn)]
+    model: &'a str,
+    /// The ID of an uploaded file with the purpose `fine-tune`.
+    training_file: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation_file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hyperparameters: Option<FineTuningHyperparameters>,
+    /// A string of up to 64 characters that will be added to the fine-tuned model name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+impl OpenAIRequestProvider for CreateFineTuningJob<'_> {
+    type Response = FineTuningJob;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/fine_tuning/jobs".to_string()
+    }
+}
+
+impl super::private::Sealed for CreateFineTuningJob<'_> {}
+
+/// The hyperparameters used for a fine-tuning job. Any that are omitted are chosen automatically by OpenAI.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Builder)]
+pub struct FineTuningHyperparameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_epochs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    learning_rate_multiplier: Option<f64>,
+}
+
+/// A request to retrieve the current state of a fine-tuning job.
+#[derive(Serialize, Debug, Clone)]
+pub struct RetrieveFineTuningJob<'a> {
+    #[serde(skip)]
+    id: &'a str,
+}
+
+impl<'a> RetrieveFineTuningJob<'a> {
+    /// Creates a request to retrieve the fine-tuning job with the provided ID.
+    pub fn new(id: &'a str) -> Self {
+        Self { id }
+    }
+}
+
+impl OpenAIRequestProvider for RetrieveFineTuningJob<'_> {
+    type Response = FineTuningJob;
+
+    const METHOD: Method = Method::GET;
+
+    fn path_with_leading_slash(&self) -> String {
+        format!("/fine_tuning/jobs/{}", self.id)
+    }
+}
+
+impl super::private::Sealed for RetrieveFineTuningJob<'_> {}
+
+/// A fine-tuning job.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FineTuningJob {
+    id: String,
+    model: String,
+    status: FineTuningJobStatus,
+    training_file: String,
+    fine_tuned_model: Option<String>,
+    created_at: i64,
+    finished_at: Option<i64>,
+    trained_tokens: Option<u64>,
+    error: Option<FineTuningJobError>,
+}
+
+impl FineTuningJob {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The base model that is being fine-tuned.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn status(&self) -> FineTuningJobStatus {
+        self.status
+    }
+
+    pub fn training_file(&self) -> &str {
+        &self.training_file
+    }
+
+    /// The name of the resulting fine-tuned model, only present once the job has succeeded.
+    pub fn fine_tuned_model(&self) -> Option<&str> {
+        self.fine_tuned_model.as_deref()
+    }
+
+    /// The unix timestamp (in seconds) of when the job was created.
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// The unix timestamp (in seconds) of when the job finished, if it has.
+    pub fn finished_at(&self) -> Option<i64> {
+        self.finished_at
+    }
+
+    pub fn trained_tokens(&self) -> Option<u64> {
+        self.trained_tokens
+    }
+
+    /// The reason the job failed, if it has.
+    pub fn error(&self) -> Option<&FineTuningJobError> {
+        self.error.as_ref()
+    }
+}
+
+/// The status of a fine-tuning job.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FineTuningJobStatus {
+    ValidatingFiles,
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// A status that this crate doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+/// Details about why a fine-tuning job failed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FineTuningJobError {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub param: Option<String>,
+}
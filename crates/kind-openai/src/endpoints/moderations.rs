@@ -0,0 +1,127 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use bon::Builder;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::OpenAIRequestProvider;
+
+/// The model used to classify content.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationModel {
+    #[serde(rename = "omni-moderation-latest")]
+    OmniModerationLatest,
+    #[serde(rename = "text-moderation-latest")]
+    TextModerationLatest,
+}
+
+/// A request to classify whether text is potentially harmful. Moderation requests are free.
+///
+/// Construct with `Moderation::input`
+#[derive(Serialize, Deserialize, Debug, Clone, Builder)]
+#[builder(start_fn = input)]
+pub struct Moderation<'a> {
+    /// The text to classify, either borrowed or owned.
+    #[builder(start_fn, into)]
+    input: Cow<'a, str>,
+    /// The model to classify the text with. Defaults to `omni-moderation-latest`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<ModerationModel>,
+}
+
+impl OpenAIRequestProvider for Moderation<'_> {
+    type Response = ModerationResponse;
+
+    const METHOD: Method = Method::POST;
+
+    fn path_with_leading_slash(&self) -> String {
+        "/moderations".to_string()
+    }
+}
+
+impl super::private::Sealed for Moderation<'_> {}
+
+/// A response from a moderation request.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModerationResponse {
+    id: String,
+    model: String,
+    results: Vec<ModerationResult>,
+}
+
+impl ModerationResponse {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The exact model that classified the input, for example `omni-moderation-2024-09-26`.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn results(&self) -> &[ModerationResult] {
+        &self.results
+    }
+
+    /// Whether or not OpenAI flagged the input as potentially harmful.
+    pub fn flagged(&self) -> bool {
+        self.results.iter().any(|result| result.flagged)
+    }
+
+    /// The categories the input was flagged in, without duplicates. See `ModerationResult::flagged_categories`.
+    pub fn flagged_categories(&self, threshold: Option<f64>) -> Vec<&str> {
+        let mut categories = self
+            .results
+            .iter()
+            .flat_map(|result| result.flagged_categories(threshold))
+            .collect::<Vec<_>>();
+        categories.sort_unstable();
+        categories.dedup();
+        categories
+    }
+}
+
+/// The classification of a single input.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModerationResult {
+    flagged: bool,
+    categories: BTreeMap<String, bool>,
+    category_scores: BTreeMap<String, f64>,
+}
+
+impl ModerationResult {
+    /// Whether or not OpenAI flagged the input as potentially harmful.
+    pub fn flagged(&self) -> bool {
+        self.flagged
+    }
+
+    /// Whether or not the input violates each category, keyed by category name (such as `harassment/threatening`).
+    pub fn categories(&self) -> &BTreeMap<String, bool> {
+        &self.categories
+    }
+
+    /// The model's confidence that the input violates each category, between 0 and 1.
+    pub fn category_scores(&self) -> &BTreeMap<String, f64> {
+        &self.category_scores
+    }
+
+    /// The categories the input was flagged in. Without a threshold these are the categories OpenAI
+    /// flagged, and with one they're the categories scored at or above it, which allows for being
+    /// stricter (or more lenient) than OpenAI's own cutoffs.
+    pub fn flagged_categories(&self, threshold: Option<f64>) -> Vec<&str> {
+        match threshold {
+            Some(threshold) => self
+                .category_scores
+                .iter()
+                .filter(|(_, score)| **score >= threshold)
+                .map(|(category, _)| category.as_str())
+                .collect(),
+            None => self
+                .categories
+                .iter()
+                .filter(|(_, flagged)| **flagged)
+                .map(|(category, _)| category.as_str())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use futures_util::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{auth::AuthTokenProvider, OpenAI, OpenAIResult};
+
+use super::OpenAIRequestProvider;
+
+/// A page of results from one of OpenAI's list endpoints, all of which share the same cursor shape.
+///
+/// To fetch the next page by hand, pass `next_cursor` as the `after` of the next request. Or use
+/// `OpenAI::paginate` to walk every page automatically.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "T: DeserializeOwned"))]
+pub struct Page<T> {
+    data: Vec<T>,
+    #[serde(default)]
+    first_id: Option<String>,
+    #[serde(default)]
+    last_id: Option<String>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+impl<T> Page<T> {
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Consumes the page and gives its items.
+    pub fn into_data(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn first_id(&self) -> Option<&str> {
+        self.first_id.as_deref()
+    }
+
+    pub fn last_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// Whether or not there are more items beyond this page.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// The cursor to pass as `after` to fetch the next page, if there is one.
+    pub fn next_cursor(&self) -> Option<&str> {
+        if self.has_more {
+            self.last_id()
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+// sends a list request starting after the provided cursor, so that `paginate` can fetch subsequent pages
+// of any list request without each one needing to know how to rewrite itself.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct AfterCursor<'r, R> {
+    request: &'r R,
+    #[serde(skip)]
+    after: &'r str,
+}
+
+impl<R> OpenAIRequestProvider for AfterCursor<'_, R>
+where
+    R: OpenAIRequestProvider,
+{
+    type Response = R::Response;
+
+    const METHOD: reqwest::Method = R::METHOD;
+
+    fn path_with_leading_slash(&self) -> String {
+        self.request.path_with_leading_slash()
+    }
+
+    // the cursor replaces whatever `after` the request itself set
+    fn query_params(&self) -> Vec<(String, String)> {
+        let mut params = self.request.query_params();
+        params.retain(|(key, _)| key != "after");
+        params.push(("after".to_owned(), self.after.to_owned()));
+        params
+    }
+
+    fn validate(&self) -> OpenAIResult<()> {
+        self.request.validate()
+    }
+}
+
+impl<R> super::private::Sealed for AfterCursor<'_, R> {}
+
+pub(crate) fn paginate<'r, Auth, R, T>(
+    openai: &'r OpenAI<Auth>,
+    request: &'r R,
+) -> impl Stream<Item = OpenAIResult<T>> + 'r
+where
+    Auth: AuthTokenProvider,
+    R: OpenAIRequestProvider<Response = Page<T>>,
+    T: DeserializeOwned + 'r,
+{
+    // `None` once there are no more pages to fetch, or once a page fails to be fetched
+    let first_page = Some(None::<String>);
+
+    futures_util::stream::unfold(first_page, move |cursor| async move {
+        let cursor = cursor?;
+        let page = match &cursor {
+            Some(after) => {
+                openai
+                    .req(&AfterCursor {
+                        request,
+                        after: after.as_str(),
+                    })
+                    .await
+            }
+            None => openai.req(request).await,
+        };
+
+        let (items, next) = match page {
+            Ok(page) => {
+                let next = page.next_cursor().map(|cursor| Some(cursor.to_owned()));
+                (page.into_data().into_iter().map(Ok).collect(), next)
+            }
+            Err(err) => (vec![Err(err)], None),
+        };
+
+        Some((futures_util::stream::iter(items), next))
+    })
+    .flatten()
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{OpenAIError, OpenAIResult};
+
+/// The parameters that control how tokens are sampled, shared by every endpoint that generates text.
+/// These are flattened into the request body, and are usually set through the request's builder rather
+/// than constructed directly.
+///
+/// Unset parameters are left out of the body entirely, which OpenAI treats the same as sending `null`. The
+/// legacy completions request always left them out, and chat requests (which used to send `null`) now do too.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SamplingParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Penalizes tokens by how often they've already appeared, between -2 and 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    /// Penalizes tokens that have appeared at all, between -2 and 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    /// Makes sampling mostly deterministic, so that repeated requests with the same seed and parameters
+    /// give the same response. OpenAI only makes a best effort at this.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "de_logit_bias"
+    )]
+    pub logit_bias: Option<HashMap<i32, i32>>,
+}
+
+impl SamplingParams {
+    /// Checks that the parameters are within the ranges accepted by OpenAI.
+    pub fn validate(&self) -> OpenAIResult<()> {
+        check_range("temperature", self.temperature, 0.0, 2.0)?;
+        check_range("top_p", self.top_p, 0.0, 1.0)?;
+        check_range("frequency_penalty", self.frequency_penalty, -2.0, 2.0)?;
+        check_range("presence_penalty", self.presence_penalty, -2.0, 2.0)?;
+
+        let out_of_range = self
+            .logit_bias
+            .iter()
+            .flatten()
+            .find(|(_, bias)| !(-100..=100).contains(*bias));
+        match out_of_range {
+            Some((token, bias)) => Err(OpenAIError::Validation(format!(
+                "logit_bias must be between -100 and 100, got {bias} for token {token}"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn check_range(name: &str, value: Option<f32>, min: f32, max: f32) -> OpenAIResult<()> {
+    match value {
+        Some(value) if !(min..=max).contains(&value) => Err(OpenAIError::Validation(format!(
+            "{name} must be between {min} and {max}, got {value}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// json object keys are always strings, and serde can't parse them back into integers on its own once
+// the params have been flattened into a request, so the keys are parsed by hand.
+fn de_logit_bias<'de, D>(deserializer: D) -> Result<Option<HashMap<i32, i32>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(logit_bias) = Option::<HashMap<String, i32>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    logit_bias
+        .into_iter()
+        .map(|(token, bias)| {
+            token
+                .parse()
+                .map(|token| (token, bias))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+// generates the setters for each sampling parameter on a request builder, which must have a
+// `#[builder(field)] sampling: SamplingParams` member. unlike regular bon setters, these can be called
+// more than once, with the last call winning.
+macro_rules! sampling_setters {
+    () => {
+        /// Replaces every sampling parameter at once.
+        pub fn sampling(mut self, sampling: $crate::endpoints::SamplingParams) -> Self {
+            self.sampling = sampling;
+            self
+        }
+
+        pub fn temperature(mut self, temperature: f32) -> Self {
+            self.sampling.temperature = Some(temperature);
+            self
+        }
+
+        pub fn top_p(mut self, top_p: f32) -> Self {
+            self.sampling.top_p = Some(top_p);
+            self
+        }
+
+        /// Penalizes tokens by how often they've already appeared, between -2 and 2.
+        pub fn frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+            self.sampling.frequency_penalty = Some(frequency_penalty);
+            self
+        }
+
+        /// Penalizes tokens that have appeared at all, between -2 and 2.
+        pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
+            self.sampling.presence_penalty = Some(presence_penalty);
+            self
+        }
+
+        /// Makes sampling mostly deterministic, so that repeated requests with the same seed and parameters
+        /// give the same response. OpenAI only makes a best effort at this.
+        pub fn seed(mut self, seed: i64) -> Self {
+            self.sampling.seed = Some(seed);
+            self
+        }
+
+        pub fn logit_bias(mut self, logit_bias: ::std::collections::HashMap<i32, i32>) -> Self {
+            self.sampling.logit_bias = Some(logit_bias);
+            self
+        }
+    };
+}
+
+pub(crate) use sampling_setters;
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{auth, OpenAI, OpenAIError, OpenAIResult};
+
+use super::{GenericOpenAIResponse, OpenAIRequestProvider, ResponseDeserializableOpenAIAPIError};
+
+/// Any request that can be sent to the client's `stream` method, producing a response that is
+/// delivered incrementally as server-sent events.
+pub trait OpenAIStreamingRequestProvider: OpenAIRequestProvider {
+    type Stream;
+
+    #[doc(hidden)]
+    fn stream_from_response(response: reqwest::Response) -> Self::Stream;
+}
+
+/// What the client does with a stream that would go over its `max_concurrent_streams`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamLimitPolicy {
+    /// Wait until one of the open streams has been dropped.
+    #[default]
+    Wait,
+    /// Fail immediately with `OpenAIError::StreamLimitReached`.
+    Reject,
+}
+
+// wraps any request and tacks on the fields that ask openai to stream the response back.
+#[derive(Serialize)]
+struct StreamingRequest<'r, R> {
+    #[serde(flatten)]
+    request: &'r R,
+    stream: bool,
+    stream_options: StreamOptions,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+pub(crate) async fn send_streaming_request<Auth, R>(
+    openai: &OpenAI<Auth>,
+    request: &R,
+) -> OpenAIResult<R::Stream>
+where
+    Auth: auth::AuthTokenProvider,
+    R: OpenAIStreamingRequestProvider,
+{
+    let body = StreamingRequest {
+        request,
+        stream: true,
+        stream_options: StreamOptions {
+            include_usage: true,
+        },
+    };
+
+    let permit = match &openai.stream_permits {
+        Some(permits) => Some(match openai.stream_limit_policy {
+            StreamLimitPolicy::Wait => permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("the stream semaphore is never closed"),
+            StreamLimitPolicy::Reject => permits
+                .clone()
+                .try_acquire_owned()
+                .map_err(|_| OpenAIError::StreamLimitReached)?,
+        }),
+        None => None,
+    };
+
+    let mut response = super::request_builder(openai, request, &body)
+        .await?
+        .send()
+        .await?;
+    openai.record_rate_limits(response.headers());
+    // the permit rides along with the response so that it's released whenever the stream holding the
+    // response is dropped, without every stream type having to know about it
+    if let Some(permit) = permit {
+        response.extensions_mut().insert(Arc::new(permit));
+    }
+
+    // errors are not streamed, they come back as a regular json body
+    if !response.status().is_success() {
+        let status = response.status();
+        let response_text = response.text().await?;
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(super::unauthorized_error(response_text));
+        }
+        return Err(
+            match serde_json::from_str::<ResponseDeserializableOpenAIAPIError>(&response_text) {
+                Ok(error) => error.error.into(),
+                Err(err) => OpenAIError::Serde(response_text, err),
+            },
+        );
+    }
+
+    Ok(R::stream_from_response(response))
+}
+
+/// A stream of server-sent events, each of which is deserialized into `T`.
+pub(crate) struct EventStream<T> {
+    response: reqwest::Response,
+    decoder: SseDecoder,
+    done: bool,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> EventStream<T>
+where
+    T: DeserializeOwned,
+{
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            decoder: SseDecoder::default(),
+            done: false,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Gives the next event in the stream, or `None` if the stream has completed.
+    pub(crate) async fn next(&mut self) -> Option<OpenAIResult<T>> {
+        loop {
+            match self.decoder.next_event() {
+                Some(SseEvent::Data(data)) => {
+                    return Some(
+                        match serde_json::from_str::<GenericOpenAIResponse<T>>(&data) {
+                            Ok(event) => event.into(),
+                            Err(err) => Err(OpenAIError::Serde(data, err)),
+                        },
+                    );
+                }
+                Some(SseEvent::Done) => {
+                    self.done = true;
+                    return None;
+                }
+                None if self.done => return None,
+                None => {}
+            }
+
+            match self.response.chunk().await {
+                Ok(Some(bytes)) => self.decoder.push(&bytes),
+                Ok(None) => {
+                    // flush out anything that wasn't terminated by a trailing newline
+                    self.decoder.push(b"\n");
+                    self.done = true;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+// dropping the response before its body has been read to the end closes the connection rather than
+// returning it to the pool, so an abandoned stream can't poison a later request. the only thing left to
+// do is to let the caller know, since the tokens of an abandoned stream are still billed.
+#[cfg(feature = "tracing")]
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        if !self.done {
+            tracing::warn!(
+                "a streamed response was dropped before it completed, closing its connection"
+            );
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SseEvent {
+    Data(String),
+    Done,
+}
+
+// a deliberately minimal server-sent events decoder. openai only ever sends single-line `data:` fields,
+// so we don't bother with multi-line data, event names, or retry fields.
+#[derive(Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn next_event(&mut self) -> Option<SseEvent> {
+        while let Some(newline) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=newline).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            let Some(data) = line.strip_prefix("data:") else {
+                // blank lines, comments, and any other fields
+                continue;
+            };
+
+            let data = data.trim_start();
+            if data == "[DONE]" {
+                return Some(SseEvent::Done);
+            }
+
+            return Some(SseEvent::Data(data.to_owned()));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_events_split_across_chunks() {
+        let mut decoder = SseDecoder::default();
+
+        decoder.push(b"data: {\"a\":");
+        assert_eq!(decoder.next_event(), None);
+
+        decoder.push(b"1}\r\n\r\n: keep-alive\n\ndata: {\"b\":2}\n\ndata: [DONE]\n\n");
+        assert_eq!(
+            decoder.next_event(),
+            Some(SseEvent::Data("{\"a\":1}".to_owned()))
+        );
+        assert_eq!(
+            decoder.next_event(),
+            Some(SseEvent::Data("{\"b\":2}".to_owned()))
+        );
+        assert_eq!(decoder.next_event(), Some(SseEvent::Done));
+        assert_eq!(decoder.next_event(), None);
+    }
+}
@@ -4,27 +4,168 @@ pub type OpenAIResult<T> = std::result::Result<T, OpenAIError>;
 
 /// Any error that can be produced during the facilitation of an OpenAI request.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum OpenAIError {
     /// Error that occured at the HTTP / request level.
-    #[error("http error: {0}")]
-    Reqwest(reqwest::Error),
+    #[error("http error")]
+    Reqwest(#[source] reqwest::Error),
+    /// The request didn't complete within the client's timeout.
+    #[error("request timed out")]
+    Timeout(#[source] reqwest::Error),
     /// Malformed response from the OpenAI API.
-    #[error("failed to deserialize api response {0} with error: {1}")]
-    Serde(String, serde_json::Error),
+    #[error("failed to deserialize api response {0}")]
+    Serde(String, #[source] serde_json::Error),
     /// The auth token was not provided.
     #[error("missing auth token")]
     MissingAuthToken,
+    /// OpenAI rejected the auth token, which it signals with a 401 status whatever else the response says.
+    /// Contains OpenAI's explanation, or the raw response body if it didn't give one.
+    #[error("OpenAI rejected the auth token: {0}")]
+    Unauthorized(String),
     /// An API error occurred.
-    #[error("OpenAI API error: {0}")]
-    API(OpenAIAPIError),
+    #[error("OpenAI API error")]
+    API(#[source] OpenAIAPIError),
+    /// OpenAI rejected the schema of a structured completion, such as for using a keyword that strict mode
+    /// doesn't support. This means that the schema type needs fixing, not the input. Contains OpenAI's
+    /// explanation of what's wrong with the schema.
+    #[error("OpenAI rejected the response format schema: {0}")]
+    InvalidSchema(String),
     /// The model refused to generate a response or could not conform to a particular structured output.
     #[error("OpenAI refused to generate response: {0}")]
     Refusal(String),
+    /// The model ran out of tokens before it finished generating a structured output, so the partial JSON
+    /// (which is included) can't be deserialized. Raising the completion token limit usually fixes this.
+    #[error("structured output was truncated because the model ran out of tokens: {0}")]
+    TruncatedStructuredOutput(String),
+    /// The request was rejected locally before being sent, because OpenAI would have rejected it anyway.
+    #[error("invalid request: {0}")]
+    Validation(String),
+    /// The client was built with an option that can't be used, such as a beta header that isn't a valid
+    /// header value. No request was involved.
+    #[error("invalid client configuration: {0}")]
+    InvalidConfig(String),
+    /// An embedding didn't have the same number of dimensions as the embeddings it was compared against,
+    /// such as when mixing embeddings from different models in an `EmbeddingIndex`. No request was involved.
+    #[error("embedding has {actual} dimensions, but {expected} were expected")]
+    DimensionMismatch { expected: usize, actual: usize },
+    /// OpenAI gave back a different number of embeddings than the inputs that were sent, so there's no
+    /// telling which embedding belongs to which input.
+    #[error("OpenAI returned {actual} embeddings for {expected} inputs")]
+    EmbeddingCountMismatch { expected: usize, actual: usize },
+    /// The input was flagged by moderation, so the request it was screening was never sent. Contains the
+    /// categories it was flagged in.
+    #[error("content was flagged by moderation: {}", .0.join(", "))]
+    Flagged(Vec<String>),
+    /// The client was already streaming as many responses as its `max_concurrent_streams` allows, and
+    /// is configured to reject any more rather than wait.
+    #[error("too many concurrent streams")]
+    StreamLimitReached,
+    /// Error that was produced by a middleware of the HTTP client, which can only happen with the
+    /// `middleware` feature enabled. The variant exists either way, so that matching on it doesn't depend on
+    /// which features are enabled.
+    #[error("middleware error")]
+    Middleware(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl OpenAIError {
+    /// Whether or not the error is transient, meaning that sending the same request again might succeed.
+    /// This is true for OpenAI server errors, rate limits, and connection or timeout failures.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OpenAIError::Reqwest(err) => err.is_connect(),
+            // one of the open streams is bound to finish eventually
+            OpenAIError::Timeout(_) | OpenAIError::StreamLimitReached => true,
+            OpenAIError::API(err) => err.is_retryable(),
+            OpenAIError::Serde(..)
+            | OpenAIError::MissingAuthToken
+            | OpenAIError::Unauthorized(_)
+            | OpenAIError::InvalidSchema(_)
+            | OpenAIError::Refusal(_)
+            | OpenAIError::TruncatedStructuredOutput(_)
+            | OpenAIError::Validation(_)
+            | OpenAIError::InvalidConfig(_)
+            | OpenAIError::DimensionMismatch { .. }
+            | OpenAIError::EmbeddingCountMismatch { .. }
+            | OpenAIError::Flagged(_)
+            | OpenAIError::Middleware(_) => false,
+        }
+    }
+
+    /// Whether or not the request timed out, which retry logic may want to handle differently from other
+    /// transient errors (such as by backing off for longer, or giving up sooner).
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, OpenAIError::Timeout(_))
+    }
+
+    /// Whether or not the error was caused by a missing or invalid auth token, as opposed to anything
+    /// else (such as the network) going wrong.
+    pub fn is_auth_error(&self) -> bool {
+        match self {
+            OpenAIError::MissingAuthToken | OpenAIError::Unauthorized(_) => true,
+            OpenAIError::API(OpenAIAPIError::InvalidRequestError(data)) => {
+                data.code.as_deref() == Some("invalid_api_key")
+            }
+            _ => false,
+        }
+    }
 }
 
 impl From<reqwest::Error> for OpenAIError {
     fn from(err: reqwest::Error) -> Self {
-        Self::Reqwest(err)
+        if err.is_timeout() {
+            Self::Timeout(err)
+        } else {
+            Self::Reqwest(err)
+        }
+    }
+}
+
+impl From<OpenAIAPIError> for OpenAIError {
+    fn from(err: OpenAIAPIError) -> Self {
+        match err {
+            // other errors can name `response_format` too (such as a model that doesn't support structured
+            // outputs at all), so only the code tells that it was the schema that got rejected
+            OpenAIAPIError::InvalidRequestError(data)
+                if data.code.as_deref() == Some("invalid_json_schema") =>
+            {
+                Self::InvalidSchema(data.message)
+            }
+            err => Self::API(err),
+        }
+    }
+}
+
+#[cfg(feature = "middleware")]
+impl From<reqwest_middleware::Error> for OpenAIError {
+    fn from(err: reqwest_middleware::Error) -> Self {
+        match err {
+            reqwest_middleware::Error::Reqwest(err) => err.into(),
+            reqwest_middleware::Error::Middleware(err) => Self::Middleware(err.into()),
+        }
+    }
+}
+
+/// Either the value that was asked for, or the refusal the model produced in its place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefusalOr<T> {
+    Value(T),
+    Refusal(String),
+}
+
+/// Extension methods on `OpenAIResult`.
+pub trait OpenAIResultExt<T> {
+    /// Separates refusals from genuine errors, for flows where a refusal is an expected outcome
+    /// that shouldn't short-circuit a `?`.
+    fn allow_refusal(self) -> OpenAIResult<RefusalOr<T>>;
+}
+
+impl<T> OpenAIResultExt<T> for OpenAIResult<T> {
+    fn allow_refusal(self) -> OpenAIResult<RefusalOr<T>> {
+        match self {
+            Ok(value) => Ok(RefusalOr::Value(value)),
+            Err(OpenAIError::Refusal(refusal)) => Ok(RefusalOr::Refusal(refusal)),
+            Err(err) => Err(err),
+        }
     }
 }
 
@@ -45,6 +186,26 @@ pub enum OpenAIAPIError {
     InternalError(OpenAIAPIErrorData),
     #[error("invalid request error: {0}")]
     InvalidRequestError(OpenAIAPIErrorData),
+    /// OpenAI tags rate limit errors with the limit that was hit, either `requests` or `tokens`.
+    #[error("rate limit exceeded: {0}")]
+    #[serde(alias = "requests", alias = "tokens")]
+    RateLimitExceeded(OpenAIAPIErrorData),
+}
+
+impl OpenAIAPIError {
+    /// Whether or not the error is transient, meaning that sending the same request again might succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OpenAIAPIError::CfServiceUnavailable(_)
+            | OpenAIAPIError::ServerError(_)
+            | OpenAIAPIError::CfBadGateway(_)
+            | OpenAIAPIError::InternalError(_)
+            | OpenAIAPIError::RateLimitExceeded(_) => true,
+            OpenAIAPIError::ContextLengthExceeded(_)
+            | OpenAIAPIError::QuotaExceeded(_)
+            | OpenAIAPIError::InvalidRequestError(_) => false,
+        }
+    }
 }
 
 /// The payload of an OpenAI API error.
@@ -58,6 +219,29 @@ pub struct OpenAIAPIErrorData {
     pub code: Option<String>,
 }
 
+impl OpenAIAPIErrorData {
+    /// A hint at how to fix the request, for errors that are commonly caused by sending a parameter
+    /// the model doesn't support (such as sampling parameters sent to a reasoning model).
+    pub fn hint(&self) -> Option<String> {
+        let param = self.param.as_deref()?;
+        let hint = match (self.code.as_deref()?, param) {
+            ("unsupported_parameter", "max_tokens") => {
+                "the model doesn't accept `max_tokens`, use `max_completion_tokens` instead".to_owned()
+            }
+            ("unsupported_parameter" | "unsupported_value", "temperature" | "top_p" | "n" | "logprobs" | "top_logprobs" | "logit_bias") => format!(
+                "reasoning models (such as o1 and o3-mini) don't support `{param}`, send the request as a `ChatReasoningCompletion` or leave `{param}` unset"
+            ),
+            ("unsupported_parameter", _) => format!("the model doesn't support the `{param}` parameter"),
+            ("unsupported_value", _) => {
+                format!("the model doesn't support the provided value of `{param}`")
+            }
+            _ => return None,
+        };
+
+        Some(hint)
+    }
+}
+
 impl std::fmt::Display for OpenAIAPIErrorData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "message: {}", self.message)?;
@@ -67,6 +251,9 @@ impl std::fmt::Display for OpenAIAPIErrorData {
         if let Some(code) = &self.code {
             write!(f, ", code: {}", code)?;
         }
+        if let Some(hint) = self.hint() {
+            write!(f, " (hint: {hint})")?;
+        }
         Ok(())
     }
 }
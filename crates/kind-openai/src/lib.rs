@@ -6,42 +6,405 @@
 mod auth;
 pub mod endpoints;
 pub mod error;
+mod rate_limits;
 
-pub use auth::{AuthTokenProvider, EnvironmentAuthTokenProvider};
-use endpoints::OpenAIRequestProvider;
-pub use error::{OpenAIError, OpenAIResult};
+pub use auth::{AuthTokenProvider, EnvironmentAuthTokenProvider, FileAuthTokenProvider};
+use bon::bon;
+use endpoints::{OpenAIRequestProvider, OpenAIStreamingRequestProvider};
+pub use error::{OpenAIError, OpenAIResult, OpenAIResultExt, RefusalOr};
+use futures_util::StreamExt;
 pub use kind_openai_schema::*;
+pub use rate_limits::RateLimitStatus;
 use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    iter::Sum,
+    ops::{Add, AddAssign},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[cfg(not(feature = "middleware"))]
+type HttpClient = reqwest::Client;
+#[cfg(feature = "middleware")]
+type HttpClient = reqwest_middleware::ClientWithMiddleware;
 
 /// A handle to OpenAI.
+///
+/// Cloning is cheap and every clone shares the same connection pool, so prefer creating a single
+/// instance and cloning it over creating a new one for every request.
 #[derive(Clone)]
 pub struct OpenAI<Auth> {
-    client: reqwest::Client,
+    client: HttpClient,
     auth: Auth,
+    base_url: String,
+    extra_headers: reqwest::header::HeaderMap,
+    // shared by every clone, since they all draw from the same rate limit budget
+    rate_limits: Arc<Mutex<Option<RateLimitStatus>>>,
+    // shared by every clone too, so that the cap applies to the client as a whole
+    stream_permits: Option<Arc<tokio::sync::Semaphore>>,
+    stream_limit_policy: endpoints::StreamLimitPolicy,
 }
 
+#[bon]
 impl<Auth> OpenAI<Auth>
 where
     Auth: AuthTokenProvider,
 {
     /// Creates a new instance of OpenAI with the provided auth.
     pub fn new(auth: Auth) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            auth,
+        Self::builder(auth)
+            .build()
+            .expect("the default configuration is always valid")
+    }
+
+    /// Creates a new instance of OpenAI with the provided auth and any additional configuration, giving
+    /// `OpenAIError::InvalidConfig` if any of it is invalid.
+    ///
+    /// Construct with `OpenAI::builder`
+    #[builder(start_fn = builder, finish_fn = build, builder_type = OpenAIBuilder)]
+    pub fn with_config(
+        #[builder(start_fn)] auth: Auth,
+        /// The base URL that all requests are sent to, for proxies or OpenAI-compatible APIs.
+        /// Defaults to `https://api.openai.com/v1`.
+        #[builder(into, default = endpoints::API_BASE_URL.to_owned())]
+        base_url: String,
+        /// How long a request may take, from connecting until the response body has been read, before it
+        /// fails with `OpenAIError::Timeout`. Applies to the whole of a stream too. Defaults to no timeout.
+        timeout: Option<Duration>,
+        /// How long an idle connection is kept in the pool before being closed. Defaults to 90 seconds.
+        pool_idle_timeout: Option<Duration>,
+        /// The maximum number of idle connections kept in the pool for each host. Defaults to no limit.
+        pool_max_idle_per_host: Option<usize>,
+        /// Headers sent with every request, such as for proxies that require their own authentication.
+        #[builder(default)]
+        mut extra_headers: reqwest::header::HeaderMap,
+        /// The `OpenAI-Beta` header sent with every request, which beta endpoints require (for example
+        /// `assistants=v2`).
+        beta: Option<&'static str>,
+        /// Whether to send `Accept-Encoding: gzip` and transparently decompress gzip responses, which
+        /// saves bandwidth on large responses such as embedding batches. Requires the `gzip` feature,
+        /// which enables reqwest's own `gzip` feature. Defaults to enabled when that feature is on.
+        #[cfg(feature = "gzip")]
+        compression: Option<bool>,
+        /// The maximum number of streamed responses that may be open at once, counting every stream until
+        /// it's dropped. Must be at least 1. Defaults to no limit.
+        max_concurrent_streams: Option<usize>,
+        /// What to do with a stream that would go over `max_concurrent_streams`. Defaults to waiting.
+        #[builder(default)]
+        stream_limit_policy: endpoints::StreamLimitPolicy,
+    ) -> OpenAIResult<Self> {
+        if let Some(beta) = beta {
+            let beta = reqwest::header::HeaderValue::from_str(beta).map_err(|_| {
+                OpenAIError::InvalidConfig(format!("{beta:?} isn't a valid OpenAI-Beta header"))
+            })?;
+            extra_headers.insert("OpenAI-Beta", beta);
+        }
+
+        if max_concurrent_streams == Some(0) {
+            return Err(OpenAIError::InvalidConfig(
+                "max_concurrent_streams must be at least 1, or no stream could ever be opened"
+                    .to_owned(),
+            ));
+        }
+
+        let mut client = reqwest::Client::builder();
+        if let Some(timeout) = timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(pool_idle_timeout) = pool_idle_timeout {
+            client = client.pool_idle_timeout(pool_idle_timeout);
+        }
+        if let Some(pool_max_idle_per_host) = pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(pool_max_idle_per_host);
         }
+        #[cfg(feature = "gzip")]
+        if let Some(compression) = compression {
+            client = client.gzip(compression);
+        }
+
+        // this mirrors `reqwest::Client::new`, which also panics if the TLS backend can't be initialized.
+        // the conversion is only needed to wrap the client when the `middleware` feature is enabled.
+        #[allow(clippy::useless_conversion)]
+        let client = client
+            .build()
+            .expect("failed to build the HTTP client")
+            .into();
+
+        Ok(Self {
+            client,
+            auth,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            extra_headers,
+            rate_limits: Arc::default(),
+            stream_permits: max_concurrent_streams
+                .map(|max| Arc::new(tokio::sync::Semaphore::new(max))),
+            stream_limit_policy,
+        })
+    }
+
+    /// Replaces the HTTP client with one that runs requests through middleware, such as for retries
+    /// or caching. Any pool settings provided to the builder don't apply to the replacement client.
+    #[cfg(feature = "middleware")]
+    pub fn with_middleware_client(self, client: reqwest_middleware::ClientWithMiddleware) -> Self {
+        Self { client, ..self }
     }
 
     /// Sends a request to the OpenAI API.
     pub async fn req<R: OpenAIRequestProvider>(&self, r: &R) -> OpenAIResult<R::Response> {
         endpoints::send_request(self, r).await
     }
+
+    /// Like `req`, but also gives the status, headers, and raw body of the response, which is useful for
+    /// debugging and for reading rate limit headers.
+    pub async fn req_raw<R: OpenAIRequestProvider>(
+        &self,
+        r: &R,
+    ) -> OpenAIResult<endpoints::RawResponse<R::Response>> {
+        endpoints::send_request_raw(self, r).await
+    }
+
+    /// Moderates the input first, and only sends the request if the input wasn't flagged, giving
+    /// `OpenAIError::Flagged` otherwise. By default OpenAI's own verdict is used, but a `threshold` flags
+    /// every category scored at or above it instead.
+    ///
+    /// Call like so: `client.screened_req(&moderation, &request).threshold(0.5).call().await`
+    #[builder]
+    pub async fn screened_req<R: OpenAIRequestProvider>(
+        &self,
+        #[builder(start_fn)] moderation: &endpoints::moderations::Moderation<'_>,
+        #[builder(start_fn)] r: &R,
+        threshold: Option<f64>,
+    ) -> OpenAIResult<R::Response> {
+        let flagged = self
+            .req(moderation)
+            .await?
+            .flagged_categories(threshold)
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        if !flagged.is_empty() {
+            return Err(OpenAIError::Flagged(flagged));
+        }
+
+        self.req(r).await
+    }
+
+    /// Embeds the same input with every model, such as for comparing models against each other, with at
+    /// most `concurrency` requests in flight at once. A failed model doesn't stop the others, so each model
+    /// is given its own result.
+    ///
+    /// Call like so: `client.embed_multi(input, &[model_a, model_b]).call().await`
+    #[builder]
+    pub async fn embed_multi(
+        &self,
+        #[builder(start_fn)] input: &str,
+        #[builder(start_fn)] models: &[endpoints::embeddings::EmbeddingsModel],
+        /// The maximum number of requests that are in flight at once.
+        #[builder(default = 4)]
+        concurrency: usize,
+    ) -> HashMap<endpoints::embeddings::EmbeddingsModel, OpenAIResult<Vec<f32>>> {
+        let requests = models
+            .iter()
+            .map(|&model| {
+                endpoints::embeddings::Embeddings::model(model)
+                    .input(input)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        let results = self.req_all(&requests, concurrency).await;
+
+        models
+            .iter()
+            .copied()
+            .zip(results)
+            .map(|(model, result)| (model, result.map(|response| response.embedding())))
+            .collect()
+    }
+
+    /// Counts the prompt tokens of the request locally, without calling the API, which is handy for
+    /// budgeting and for checking that a prompt fits in the model's context window.
+    #[cfg(feature = "tokenizer")]
+    pub fn count_tokens<R: endpoints::chat::CountTokens>(&self, r: &R) -> usize {
+        r.count_prompt_tokens()
+    }
+
+    /// Sends every request to the OpenAI API with at most `concurrency` of them in flight at once,
+    /// giving the results in the same order as the requests. A failed request doesn't stop the others.
+    pub async fn req_all<'r, R, I>(
+        &self,
+        requests: I,
+        concurrency: usize,
+    ) -> Vec<OpenAIResult<R::Response>>
+    where
+        R: OpenAIRequestProvider + 'r,
+        I: IntoIterator<Item = &'r R>,
+    {
+        futures_util::stream::iter(requests)
+            .map(|request| self.req(request))
+            // `buffered` both caps how many requests are in flight and keeps the results in order
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Walks every page of a list request, starting from the page the request asks for, and gives each
+    /// item in order. Subsequent pages are only fetched once the items of the previous page have been
+    /// consumed, and the stream ends after the first error.
+    pub fn paginate<'r, R, T>(
+        &'r self,
+        request: &'r R,
+    ) -> impl futures_util::Stream<Item = OpenAIResult<T>> + 'r
+    where
+        R: OpenAIRequestProvider<Response = endpoints::Page<T>>,
+        T: for<'de> Deserialize<'de> + 'r,
+    {
+        endpoints::paginate(self, request)
+    }
+
+    /// Checks that OpenAI accepts the auth token, without making a billable request. Use
+    /// `OpenAIError::is_auth_error` to tell a rejected token apart from a network failure.
+    pub async fn validate_auth(&self) -> OpenAIResult<()> {
+        self.req(&endpoints::ListModels).await.map(|_| ())
+    }
+
+    /// The rate limit budget reported on the most recent response that included it, which is shared by
+    /// every clone of the client. `None` until such a response has been received.
+    pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+        *self
+            .rate_limits
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Sends a request to the OpenAI API, streaming the response back as it's generated.
+    pub async fn stream<R: OpenAIStreamingRequestProvider>(
+        &self,
+        r: &R,
+    ) -> OpenAIResult<R::Stream> {
+        endpoints::send_streaming_request(self, r).await
+    }
+}
+
+impl<Auth> OpenAI<Auth> {
+    // called with the headers of every response, so that `rate_limit_status` stays up to date.
+    pub(crate) fn record_rate_limits(&self, headers: &reqwest::header::HeaderMap) {
+        if let Some(status) = RateLimitStatus::from_headers(headers) {
+            *self
+                .rate_limits
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(status);
+        }
+    }
 }
 
 /// The token usage of a request.
-#[derive(Deserialize, Clone, Copy, Debug)]
+///
+/// Usage can be added together to total the tokens spent across many requests, either with `+` or with
+/// `Usage::sum`. The prompt token breakdown is kept as long as at least one of the requests reported one.
+/// Totals saturate at `u32::MAX` rather than overflowing.
+///
+/// OpenAI keeps reporting more about usage, so new fields may be added and usage can't be built with a
+/// struct literal outside of this crate.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Usage {
     pub prompt_tokens: u32,
+    /// Zero for embeddings, which don't generate anything and leave this out of their usage.
+    #[serde(default)]
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// A breakdown of the prompt tokens, which isn't present on every response.
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+impl Usage {
+    /// The number of prompt tokens that were served from OpenAI's prompt cache.
+    pub fn cached_tokens(&self) -> u32 {
+        self.prompt_tokens_details
+            .map(|details| details.cached_tokens)
+            .unwrap_or_default()
+    }
+
+    /// The fraction of prompt tokens, from `0.0` to `1.0`, that were served from the prompt cache.
+    ///
+    /// OpenAI caches long, stable prompt prefixes automatically, so keeping your system prompt and any
+    /// other unchanging context at the start of the messages is what gets this above zero.
+    pub fn cache_hit_ratio(&self) -> f64 {
+        if self.prompt_tokens == 0 {
+            return 0.0;
+        }
+
+        self.cached_tokens() as f64 / self.prompt_tokens as f64
+    }
+
+    /// The estimated number of completion tokens spent on each of the provided number of choices.
+    /// OpenAI only reports the total across every choice, so this is an even split of that total,
+    /// which is useful for allocating cost when only one of several choices is used.
+    pub fn completion_tokens_per_choice(&self, choices: usize) -> f64 {
+        if choices == 0 {
+            return 0.0;
+        }
+
+        self.completion_tokens as f64 / choices as f64
+    }
+
+    /// Totals every usage, giving zeroed usage if there are none.
+    pub fn sum(usages: impl IntoIterator<Item = Usage>) -> Usage {
+        usages.into_iter().fold(Usage::default(), Add::add)
+    }
+}
+
+impl Add for Usage {
+    type Output = Usage;
+
+    fn add(self, rhs: Usage) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_tokens.saturating_add(rhs.prompt_tokens),
+            completion_tokens: self.completion_tokens.saturating_add(rhs.completion_tokens),
+            total_tokens: self.total_tokens.saturating_add(rhs.total_tokens),
+            prompt_tokens_details: match (self.prompt_tokens_details, rhs.prompt_tokens_details) {
+                (Some(lhs), Some(rhs)) => Some(lhs + rhs),
+                (details, None) | (None, details) => details,
+            },
+        }
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, rhs: Usage) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Usage {
+    fn sum<I: Iterator<Item = Usage>>(iter: I) -> Usage {
+        Usage::sum(iter)
+    }
+}
+
+impl<'a> Sum<&'a Usage> for Usage {
+    fn sum<I: Iterator<Item = &'a Usage>>(iter: I) -> Usage {
+        Usage::sum(iter.copied())
+    }
+}
+
+/// A breakdown of the prompt tokens of a request.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PromptTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: u32,
+}
+
+impl Add for PromptTokensDetails {
+    type Output = PromptTokensDetails;
+
+    fn add(self, rhs: PromptTokensDetails) -> PromptTokensDetails {
+        PromptTokensDetails {
+            cached_tokens: self.cached_tokens.saturating_add(rhs.cached_tokens),
+        }
+    }
 }
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+
+/// The rate limit budget that OpenAI reported on a response, for throttling before hitting a 429.
+///
+/// Each field is `None` when its header wasn't sent, which is common with proxies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// How many requests can still be made before the request limit is hit.
+    pub remaining_requests: Option<u64>,
+    /// How many tokens can still be used before the token limit is hit.
+    pub remaining_tokens: Option<u64>,
+    /// How long until the request limit resets to its initial state.
+    pub reset_requests: Option<Duration>,
+    /// How long until the token limit resets to its initial state.
+    pub reset_tokens: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// Reads the `x-ratelimit-remaining-*` and `x-ratelimit-reset-*` headers, giving `None` if the response
+    /// had none of them.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        let status = Self {
+            remaining_requests: header("x-ratelimit-remaining-requests")
+                .and_then(|value| value.parse().ok()),
+            remaining_tokens: header("x-ratelimit-remaining-tokens")
+                .and_then(|value| value.parse().ok()),
+            reset_requests: header("x-ratelimit-reset-requests").and_then(parse_reset),
+            reset_tokens: header("x-ratelimit-reset-tokens").and_then(parse_reset),
+        };
+
+        (status != Self::default()).then_some(status)
+    }
+}
+
+// the resets are formatted like go durations, such as `1s`, `6m0s`, or `20ms`.
+fn parse_reset(value: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .unwrap_or(rest.len());
+        let number = rest[..number_len].parse::<f64>().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|ch: char| ch.is_ascii_digit() || ch == '.')
+            .unwrap_or(rest.len());
+        let seconds_per_unit = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+
+        total += Duration::try_from_secs_f64(number * seconds_per_unit).ok()?;
+    }
+
+    Some(total)
+}
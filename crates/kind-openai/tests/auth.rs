@@ -0,0 +1,66 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use kind_openai::{AuthTokenProvider, FileAuthTokenProvider};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kind-openai-{}-{name}", std::process::id()))
+}
+
+// writes the token and pins the modification time, since some filesystems only track it to the second
+fn write_token(path: &PathBuf, token: &str, modified: SystemTime) {
+    let mut file = File::create(path).unwrap();
+    file.write_all(token.as_bytes()).unwrap();
+    file.set_modified(modified).unwrap();
+}
+
+#[tokio::test]
+async fn it_reloads_the_token_when_the_file_changes() {
+    let path = temp_path("rotating");
+    let start = SystemTime::now();
+    write_token(&path, "sk-first\n", start);
+
+    let provider = FileAuthTokenProvider::new(&path);
+    assert_eq!(provider.resolve().await.as_deref(), Some("sk-first"));
+
+    write_token(&path, "sk-second\n", start + Duration::from_secs(60));
+    assert_eq!(
+        provider.clone().resolve().await.as_deref(),
+        Some("sk-second")
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn it_serves_the_cached_token_while_the_file_is_unchanged() {
+    let path = temp_path("cached");
+    let modified = SystemTime::now();
+    write_token(&path, "sk-cached", modified);
+
+    let provider = FileAuthTokenProvider::new(&path);
+    assert_eq!(provider.resolve().await.as_deref(), Some("sk-cached"));
+
+    // same modification time, so the file isn't read again
+    write_token(&path, "sk-unseen", modified);
+    assert_eq!(provider.resolve().await.as_deref(), Some("sk-cached"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn it_provides_no_token_for_missing_or_empty_files() {
+    let path = temp_path("missing");
+    let provider = FileAuthTokenProvider::new(&path);
+    assert_eq!(provider.resolve().await, None);
+
+    write_token(&path, "  \n", SystemTime::now());
+    assert_eq!(provider.resolve().await, None);
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(provider.resolve().await, None);
+}
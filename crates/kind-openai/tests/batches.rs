@@ -0,0 +1,87 @@
+use kind_openai::endpoints::{
+    batches::{Batch, BatchEndpoint, BatchStatus, CancelBatch, CreateBatch},
+    OpenAIRequestProvider,
+};
+
+#[test]
+fn it_deserializes_an_in_progress_batch() {
+    let batch: Batch = serde_json::from_str(
+        r#"{
+            "id": "batch_abc123",
+            "object": "batch",
+            "endpoint": "/v1/chat/completions",
+            "errors": null,
+            "input_file_id": "file-abc123",
+            "completion_window": "24h",
+            "status": "in_progress",
+            "output_file_id": null,
+            "error_file_id": null,
+            "created_at": 1711471533,
+            "in_progress_at": 1711471538,
+            "completed_at": null,
+            "request_counts": {"total": 100, "completed": 42, "failed": 1},
+            "metadata": {"customer_id": "user_123456789"}
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(batch.id(), "batch_abc123");
+    assert_eq!(batch.status(), BatchStatus::InProgress);
+    assert_eq!(batch.output_file_id(), None);
+    assert_eq!(batch.request_counts().unwrap().completed, 42);
+    assert_eq!(batch.metadata().unwrap()["customer_id"], "user_123456789");
+}
+
+#[test]
+fn it_deserializes_a_completed_batch() {
+    let batch: Batch = serde_json::from_str(
+        r#"{
+            "id": "batch_abc123",
+            "object": "batch",
+            "endpoint": "/v1/embeddings",
+            "input_file_id": "file-abc123",
+            "completion_window": "24h",
+            "status": "completed",
+            "output_file_id": "file-cvaTdG",
+            "error_file_id": "file-HOWS94",
+            "created_at": 1711471533,
+            "completed_at": 1711493163,
+            "request_counts": {"total": 100, "completed": 95, "failed": 5},
+            "metadata": null
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(batch.status(), BatchStatus::Completed);
+    assert_eq!(batch.output_file_id(), Some("file-cvaTdG"));
+    assert_eq!(batch.error_file_id(), Some("file-HOWS94"));
+    assert_eq!(batch.completed_at(), Some(1711493163));
+}
+
+#[test]
+fn it_falls_back_to_other_for_unknown_statuses() {
+    assert_eq!(
+        serde_json::from_str::<BatchStatus>(r#""paused""#).unwrap(),
+        BatchStatus::Other
+    );
+}
+
+#[test]
+fn it_builds_batch_requests() {
+    let create = CreateBatch::endpoint(BatchEndpoint::Embeddings)
+        .input_file_id("file-abc123")
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&create).unwrap(),
+        serde_json::json!({
+            "endpoint": "/v1/embeddings",
+            "input_file_id": "file-abc123",
+            "completion_window": "24h",
+        })
+    );
+    assert_eq!(
+        CancelBatch::new("batch_abc123").path_with_leading_slash(),
+        "/batches/batch_abc123/cancel"
+    );
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use kind_openai::{endpoints::chat_reasoning::ReasoningModel, OpenAIError};
+
+#[test]
+#[allow(deprecated)]
+fn it_still_serializes_deprecated_models() {
+    assert_eq!(
+        serde_json::to_value(ReasoningModel::O1Preview).unwrap(),
+        "o1-preview"
+    );
+    assert_eq!(
+        serde_json::to_value(ReasoningModel::O1Mini_2024_09_12).unwrap(),
+        "o1-mini-2024-09-12"
+    );
+}
+
+#[test]
+fn it_deserializes_the_completion_metadata() {
+    let response: kind_openai::endpoints::chat_reasoning::ChatReasoningCompletionResponse =
+        serde_json::from_str(
+            r#"{
+                "id": "chatcmpl-789",
+                "object": "chat.completion",
+                "created": 1734000000,
+                "model": "o1-2024-12-17",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "42", "refusal": null },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 12, "completion_tokens": 300, "total_tokens": 312 }
+            }"#,
+        )
+        .unwrap();
+
+    assert_eq!(response.id(), "chatcmpl-789");
+    assert_eq!(response.created(), 1734000000);
+    assert_eq!(response.model(), "o1-2024-12-17");
+    assert_eq!(
+        response.take_first_choice().unwrap().message().unwrap(),
+        "42"
+    );
+}
+
+#[test]
+fn it_exposes_the_finish_reason_refusal_and_usage() {
+    use kind_openai::endpoints::{
+        chat::{CompletionResponse, FinishReason},
+        chat_reasoning::ChatReasoningCompletionResponse,
+    };
+
+    let response: ChatReasoningCompletionResponse = serde_json::from_str(
+        r#"{
+            "id": "chatcmpl-789",
+            "object": "chat.completion",
+            "created": 1734000000,
+            "model": "o1-2024-12-17",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": null, "refusal": "I can't help with that." },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 300, "total_tokens": 312 }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+    assert_eq!(response.refusal(), Some("I can't help with that."));
+    assert_eq!(response.usage().unwrap().total_tokens, 312);
+
+    let choice = response.take_first_choice().unwrap();
+    assert_eq!(choice.refusal(), Some("I can't help with that."));
+    assert!(matches!(
+        choice.message(),
+        Err(OpenAIError::Refusal(refusal)) if refusal == "I can't help with that."
+    ));
+}
+
+#[test]
+fn it_only_serializes_the_user_when_set() {
+    use kind_openai::{
+        endpoints::chat_reasoning::{ChatReasoningCompletion, ReasoningModel},
+        reasoning_user_message,
+    };
+
+    let request = ChatReasoningCompletion::model(ReasoningModel::O1)
+        .messages(vec![reasoning_user_message!("What is 40 + 2?")])
+        .store(true)
+        .user("user-1234")
+        .build();
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["user"], "user-1234");
+    assert_eq!(json["store"], true);
+
+    let request = ChatReasoningCompletion::model(ReasoningModel::O1)
+        .messages(vec![reasoning_user_message!("What is 40 + 2?")])
+        .build();
+    let json = serde_json::to_value(&request).unwrap();
+    assert!(!json.as_object().unwrap().contains_key("user"));
+}
+
+mod streaming {
+    use kind_openai::{
+        endpoints::chat_reasoning::{
+            ChatReasoningCompletion, ReasoningCompletionDelta, ReasoningModel,
+        },
+        reasoning_user_message, OpenAI,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    #[tokio::test]
+    async fn it_streams_summary_and_content_deltas_separately() {
+        let events = [
+            r#"{"choices":[{"index":0,"delta":{"role":"assistant","content":""},"finish_reason":null}],"usage":null}"#,
+            r#"{"choices":[{"index":0,"delta":{"reasoning_summary":"Adding the "},"finish_reason":null}],"usage":null}"#,
+            r#"{"choices":[{"index":0,"delta":{"reasoning_summary":"numbers."},"finish_reason":null}],"usage":null}"#,
+            r#"{"choices":[{"index":0,"delta":{"reasoning_summary":"","content":"4"},"finish_reason":null}],"usage":null}"#,
+            r#"{"choices":[{"index":0,"delta":{"content":"2"},"finish_reason":"stop"}],"usage":null}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":300,"total_tokens":312}}"#,
+        ];
+        let body = events
+            .iter()
+            .map(|event| format!("data: {event}\n\n"))
+            .chain(["data: [DONE]\n\n".to_owned()])
+            .collect::<String>();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let request = ChatReasoningCompletion::model(ReasoningModel::O1)
+            .messages(vec![reasoning_user_message!("What is 40 + 2?")])
+            .build();
+        let mut stream = client.stream(&request).await.unwrap();
+
+        let mut deltas = Vec::new();
+        while let Some(delta) = stream.next().await {
+            deltas.push(delta.unwrap());
+        }
+
+        assert_eq!(
+            deltas,
+            [
+                ReasoningCompletionDelta::Summary("Adding the ".to_owned()),
+                ReasoningCompletionDelta::Summary("numbers.".to_owned()),
+                ReasoningCompletionDelta::Content("4".to_owned()),
+                ReasoningCompletionDelta::Content("2".to_owned()),
+            ]
+        );
+        assert_eq!(stream.usage().unwrap().total_tokens, 312);
+    }
+}
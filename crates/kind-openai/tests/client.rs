@@ -0,0 +1,428 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use common::TestAuth;
+use kind_openai::{endpoints::files::ListFiles, OpenAI};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Starts a keep-alive HTTP server that answers every request with an empty file list, giving its
+/// address and the number of connections that have been accepted.
+async fn start_counting_server() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let body = r#"{"object":"list","data":[],"has_more":false}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                let mut buf = Vec::new();
+                let mut chunk = [0; 1024];
+                loop {
+                    match socket.read(&mut chunk).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                    }
+                    // every request the client sends here is a bodiless GET
+                    while let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        buf.drain(..end + 4);
+                        if socket.write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    (address, connections)
+}
+
+#[tokio::test]
+async fn clones_share_the_connection_pool() {
+    let (address, connections) = start_counting_server().await;
+
+    let client = OpenAI::builder(TestAuth).base_url(address).build().unwrap();
+    let clone = client.clone();
+
+    for _ in 0..3 {
+        client.req(&ListFiles::new()).await.unwrap();
+        clone.req(&ListFiles::new()).await.unwrap();
+    }
+
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn it_builds_a_client_with_custom_pool_limits() {
+    let (address, connections) = start_counting_server().await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(address)
+        .pool_idle_timeout(Duration::from_secs(30))
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+
+    client.req(&ListFiles::new()).await.unwrap();
+    client.req(&ListFiles::new()).await.unwrap();
+
+    // with no idle connections allowed, nothing can be reused between requests
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+mod req_all {
+    use std::sync::atomic::Ordering;
+
+    use kind_openai::{
+        endpoints::{batches::RetrieveBatch, files::ListFiles},
+        OpenAI, OpenAIError,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::{start_counting_server, TestAuth};
+
+    fn batch(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "object": "batch",
+            "endpoint": "/v1/chat/completions",
+            "input_file_id": "file-abc123",
+            "status": "completed",
+            "output_file_id": null,
+            "error_file_id": null,
+            "created_at": 1711471533,
+            "completed_at": null,
+            "request_counts": null,
+            "metadata": null,
+        })
+    }
+
+    #[tokio::test]
+    async fn it_gives_results_in_order_without_aborting_on_errors() {
+        let server = MockServer::start().await;
+        for id in ["batch_1", "batch_3"] {
+            Mock::given(method("GET"))
+                .and(path(format!("/batches/{id}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        // delay the first response so that it finishes after the others
+                        .set_delay(std::time::Duration::from_millis(if id == "batch_1" {
+                            100
+                        } else {
+                            0
+                        }))
+                        .set_body_json(batch(id)),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/batches/batch_2"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": "No batch found with id 'batch_2'.",
+                    "param": null,
+                    "code": null,
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let requests = ["batch_1", "batch_2", "batch_3"].map(RetrieveBatch::new);
+        let results = client.req_all(&requests, 3).await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().id(), "batch_1");
+        assert!(matches!(results[1], Err(OpenAIError::API(_))));
+        assert_eq!(results[2].as_ref().unwrap().id(), "batch_3");
+    }
+
+    #[tokio::test]
+    async fn it_handles_no_requests() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        // nothing is listening once the listener is dropped, so any request that was sent would fail
+        drop(listener);
+
+        let client = OpenAI::builder(TestAuth).base_url(address).build().unwrap();
+
+        assert!(client.req_all::<RetrieveBatch, _>([], 0).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_never_exceeds_the_concurrency_limit() {
+        let (address, connections) = start_counting_server().await;
+        let client = OpenAI::builder(TestAuth).base_url(address).build().unwrap();
+
+        let requests = vec![ListFiles::new(); 5];
+        let results = client.req_all(&requests, 1).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        // one request at a time means one connection is enough for all of them
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+}
+
+mod req_raw {
+    use kind_openai::{endpoints::files::ListFiles, OpenAI};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::TestAuth;
+
+    #[tokio::test]
+    async fn it_gives_the_status_headers_and_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining-requests", "59")
+                    .insert_header("x-request-id", "req_123")
+                    .set_body_string(r#"{"object":"list","data":[],"has_more":false}"#),
+            )
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let raw = client.req_raw(&ListFiles::new()).await.unwrap();
+
+        assert_eq!(raw.status(), 200);
+        assert_eq!(raw.headers()["x-ratelimit-remaining-requests"], "59");
+        assert_eq!(raw.headers()["x-request-id"], "req_123");
+        assert_eq!(
+            raw.body(),
+            r#"{"object":"list","data":[],"has_more":false}"#
+        );
+        assert!(raw.into_response().files().is_empty());
+    }
+}
+
+mod validate_auth {
+    use kind_openai::{endpoints::files::ListFiles, OpenAI, OpenAIError};
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::TestAuth;
+
+    #[tokio::test]
+    async fn it_accepts_a_valid_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer test"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"object":"list","data":[{"id":"gpt-4o","object":"model","created":1715367049,"owned_by":"system"}]}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        client.validate_auth().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_reports_a_rejected_token_as_an_auth_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string(
+                r#"{"error":{"message":"Incorrect API key provided: test.","type":"invalid_request_error","param":null,"code":"invalid_api_key"}}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let err = client.validate_auth().await.unwrap_err();
+        assert!(err.is_auth_error());
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn it_reports_any_401_as_an_auth_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string(
+                r#"{"error":{"message":"You didn't provide an API key.","type":"invalid_request_error","param":null,"code":null}}"#,
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let err = client.validate_auth().await.unwrap_err();
+        assert!(err.is_auth_error());
+        assert!(
+            matches!(&err, OpenAIError::Unauthorized(message) if message == "You didn't provide an API key.")
+        );
+
+        // a proxy in front of OpenAI might not answer with json at all
+        let err = client.req(&ListFiles::new()).await.unwrap_err();
+        assert!(err.is_auth_error());
+        assert!(matches!(&err, OpenAIError::Unauthorized(message) if message == "Unauthorized"));
+    }
+
+    #[tokio::test]
+    async fn it_doesnt_mistake_network_failures_for_auth_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        // nothing is listening once the listener is dropped, so the connection is refused
+        drop(listener);
+
+        let client = OpenAI::builder(TestAuth).base_url(address).build().unwrap();
+        let err = client.validate_auth().await.unwrap_err();
+        assert!(!err.is_auth_error());
+        assert!(err.is_retryable());
+    }
+}
+
+mod extra_headers {
+    use kind_openai::{endpoints::files::ListFiles, OpenAI, OpenAIError};
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::TestAuth;
+
+    #[tokio::test]
+    async fn it_sends_the_extra_headers_with_every_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .and(header("x-proxy-key", "secret"))
+            .and(header("openai-beta", "assistants=v2"))
+            .and(header("authorization", "Bearer test"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"object":"list","data":[],"has_more":false}"#),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut extra_headers = HeaderMap::new();
+        extra_headers.insert("x-proxy-key", HeaderValue::from_static("secret"));
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .extra_headers(extra_headers)
+            .beta("assistants=v2")
+            .build()
+            .unwrap();
+
+        client.req(&ListFiles::new()).await.unwrap();
+        client.req(&ListFiles::new()).await.unwrap();
+    }
+
+    #[test]
+    fn it_rejects_a_beta_header_that_isnt_a_valid_header_value() {
+        let result = OpenAI::builder(TestAuth).beta("assistants=v2\n").build();
+
+        assert!(matches!(result, Err(OpenAIError::InvalidConfig(_))));
+    }
+}
+
+mod rate_limit_status {
+    use std::time::Duration;
+
+    use kind_openai::{endpoints::files::ListFiles, OpenAI, RateLimitStatus};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::TestAuth;
+
+    const EMPTY_LIST: &str = r#"{"object":"list","data":[],"has_more":false}"#;
+
+    #[tokio::test]
+    async fn it_tracks_the_latest_rate_limit_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(EMPTY_LIST)
+                    .insert_header("x-ratelimit-remaining-requests", "59")
+                    .insert_header("x-ratelimit-remaining-tokens", "149984")
+                    .insert_header("x-ratelimit-reset-requests", "1m0.5s")
+                    .insert_header("x-ratelimit-reset-tokens", "20ms"),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        // proxies may leave the headers out, which shouldn't clear what was already seen
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(EMPTY_LIST))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        assert_eq!(client.rate_limit_status(), None);
+
+        // clones share the status, since they share the same budget
+        client.clone().req(&ListFiles::new()).await.unwrap();
+        let expected = RateLimitStatus {
+            remaining_requests: Some(59),
+            remaining_tokens: Some(149984),
+            reset_requests: Some(Duration::from_millis(60_500)),
+            reset_tokens: Some(Duration::from_millis(20)),
+        };
+        assert_eq!(client.rate_limit_status(), Some(expected));
+
+        client.req(&ListFiles::new()).await.unwrap();
+        assert_eq!(client.rate_limit_status(), Some(expected));
+    }
+}
@@ -0,0 +1,23 @@
+// every test binary includes this module but only uses some of it
+#![allow(dead_code)]
+
+use kind_openai::AuthTokenProvider;
+
+/// Auth that always resolves to the same token, for tests that run against a mock server.
+#[derive(Clone)]
+pub struct TestAuth;
+
+impl AuthTokenProvider for TestAuth {
+    async fn resolve(&self) -> Option<String> {
+        Some("test".to_owned())
+    }
+}
+
+/// The address of a port that nothing listens on, so that connecting to it is refused. The port is
+/// picked by binding to it and letting it go, rather than assuming that some fixed port is free.
+pub async fn refused_address() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    address
+}
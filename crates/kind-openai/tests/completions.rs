@@ -0,0 +1,94 @@
+use kind_openai::endpoints::{
+    chat::FinishReason,
+    completions::{Completion, CompletionResponse},
+};
+
+#[test]
+fn it_serializes_a_completion_request() {
+    let request = Completion::model("gpt-3.5-turbo-instruct")
+        .prompt("Once upon a time")
+        .max_tokens(16)
+        .stop(vec!["\n"])
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({
+            "model": "gpt-3.5-turbo-instruct",
+            "prompt": "Once upon a time",
+            "max_tokens": 16,
+            "stop": ["\n"],
+        })
+    );
+}
+
+#[test]
+fn it_flattens_the_sampling_params_into_the_request() {
+    let request = Completion::model("davinci-002")
+        .prompt("Once upon a time")
+        .temperature(0.5)
+        .top_p(0.75)
+        .frequency_penalty(0.5)
+        .presence_penalty(-0.5)
+        .seed(42)
+        .logit_bias(kind_openai::logit_bias!(50256: -100))
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({
+            "model": "davinci-002",
+            "prompt": "Once upon a time",
+            "temperature": 0.5,
+            "top_p": 0.75,
+            "frequency_penalty": 0.5,
+            "presence_penalty": -0.5,
+            "seed": 42,
+            "logit_bias": { "50256": -100 },
+        })
+    );
+}
+
+#[test]
+fn it_deserializes_a_completion_response() {
+    let response: CompletionResponse = serde_json::from_str(
+        r#"{
+            "id": "cmpl-uqkvlQyYK7bGYrRHQ0eXlWi7",
+            "object": "text_completion",
+            "created": 1589478378,
+            "model": "gpt-3.5-turbo-instruct",
+            "system_fingerprint": "fp_44709d6fcb",
+            "choices": [
+                {
+                    "text": ", there was a dragon.",
+                    "index": 0,
+                    "logprobs": null,
+                    "finish_reason": "length"
+                }
+            ],
+            "usage": { "prompt_tokens": 4, "completion_tokens": 7, "total_tokens": 11 }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(response.usage().unwrap().total_tokens, 11);
+
+    let choice = response.take_first_choice().unwrap();
+    assert_eq!(choice.index(), 0);
+    assert!(matches!(choice.finish_reason(), FinishReason::Length));
+    assert_eq!(choice.text(), ", there was a dragon.");
+}
+
+#[test]
+fn it_deserializes_a_completion_response_without_usage() {
+    let response: CompletionResponse = serde_json::from_str(
+        r#"{
+            "choices": [
+                { "text": "Hi!", "index": 0, "logprobs": null, "finish_reason": "stop" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert!(response.usage().is_none());
+}
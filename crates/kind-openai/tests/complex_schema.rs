@@ -1,4 +1,4 @@
-use kind_openai_schema::OpenAISchema;
+use kind_openai_schema::{assert_schema_eq, OpenAISchema};
 use serde::Deserialize;
 
 #[test]
@@ -25,10 +25,921 @@ fn it_generates_complex_schema_correctly() {
         ReallyLong,
     }
 
+    assert_schema_eq!(
+        SuperComplexSchema,
+        r#"
+        {
+            "name": "SuperComplexSchema",
+            "description": "Hello friends",
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "optional_string": {
+                        "description": "The first one.",
+                        "type": ["string", "null"]
+                    },
+                    "regular_string": { "type": "string" },
+                    "int": { "type": "integer" },
+                    "basic_enum": {
+                        "enum": [
+                            "variant1",
+                            "Variant2",
+                            "this-has-a-really-really-long-name-that-strangely-broke-some-thing-with-this-maybe-the-attrs-have-a-max-len-i-dont-really-know"
+                        ],
+                        "type": "string"
+                    }
+                },
+                "required": ["optional_string", "regular_string", "int", "basic_enum"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_excludes_defaulted_fields_from_required() {
+    #[derive(Deserialize, OpenAISchema)]
+    struct WithDefaults {
+        name: String,
+        #[serde(default)]
+        nickname: String,
+        #[serde(default = "default_age", rename = "years")]
+        age: u32,
+    }
+
+    fn default_age() -> u32 {
+        42
+    }
+
+    assert_schema_eq!(
+        WithDefaults,
+        r#"
+        {
+            "name": "WithDefaults",
+            "description": null,
+            "strict": false,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "name": { "type": "string" },
+                    "nickname": { "type": "string" },
+                    "years": { "type": "integer" }
+                },
+                "required": ["name"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_computes_implicit_discriminants_for_repr_enums() {
+    #[derive(serde_repr::Deserialize_repr, OpenAISchema)]
+    #[repr(i8)]
+    enum Mixed {
+        A = 1,
+        B,
+        C = 10,
+        D,
+        E = -3,
+        F,
+    }
+
+    #[derive(serde_repr::Deserialize_repr, OpenAISchema)]
+    #[repr(u8)]
+    enum ImplicitStart {
+        Zero,
+        One,
+        Five = 5,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct WithDiscriminants {
+        mixed: Mixed,
+        implicit_start: ImplicitStart,
+    }
+
+    assert_schema_eq!(
+        WithDiscriminants,
+        r#"
+        {
+            "name": "WithDiscriminants",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "mixed": { "enum": [1, 2, 10, 11, -3, -2], "type": "number" },
+                    "implicit_start": { "enum": [0, 1, 5], "type": "number" }
+                },
+                "required": ["mixed", "implicit_start"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_compares_schemas_ignoring_key_order() {
+    #[derive(Deserialize, OpenAISchema)]
+    struct Small {
+        flag: bool,
+    }
+
+    let expected = r#"{
+        "strict": true,
+        "schema": {
+            "required": ["flag"],
+            "properties": { "flag": { "type": "boolean" } },
+            "additionalProperties": false,
+            "type": "object"
+        },
+        "description": null,
+        "name": "Small"
+    }"#;
+
+    assert!(Small::openai_schema().matches(expected));
+    assert!(!Small::openai_schema().matches(&expected.replace("boolean", "string")));
+}
+
+#[test]
+#[should_panic(expected = "$.schema.properties.flag.type: expected \"string\", got \"boolean\"")]
+#[allow(dead_code)]
+fn it_reports_where_schemas_differ() {
+    #[derive(Deserialize, OpenAISchema)]
+    struct Small {
+        flag: bool,
+    }
+
+    assert_schema_eq!(
+        Small,
+        r#"
+        {
+            "name": "Small",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": { "flag": { "type": "string" } },
+                "required": ["flag"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_handles_combined_serde_field_attributes() {
+    #[derive(Deserialize, OpenAISchema)]
+    struct Combined {
+        #[serde(alias = "fullName", rename = "full_name")]
+        name: String,
+        #[serde(alias = "nick", default, skip_serializing_if = "Option::is_none")]
+        nickname: Option<String>,
+        #[serde(
+            skip_serializing_if = "Vec::is_empty",
+            rename = "tag_list",
+            alias = "tags"
+        )]
+        tags: Vec<String>,
+        #[serde(rename(serialize = "out", deserialize = "incoming"))]
+        direction: String,
+        #[serde(skip_deserializing)]
+        internal: u32,
+    }
+
+    assert_schema_eq!(
+        Combined,
+        r#"
+        {
+            "name": "Combined",
+            "description": null,
+            "strict": false,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "full_name": { "type": "string" },
+                    "nickname": { "type": ["string", "null"] },
+                    "tag_list": { "type": "array", "items": { "type": "string" } },
+                    "incoming": { "type": "string" }
+                },
+                "required": ["full_name", "tag_list", "incoming"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_only_skips_what_serde_skips() {
+    #[derive(Deserialize, OpenAISchema)]
+    enum Status {
+        #[serde(alias = "on", rename = "active")]
+        Active,
+        #[serde(skip_serializing, rename = "idle")]
+        Idle,
+        #[serde(skip)]
+        Internal,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Skippable {
+        #[serde(rename = "skip")]
+        skip_count: u32,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "note")]
+        comment: Option<String>,
+        status: Status,
+        #[serde(skip)]
+        cache: Vec<String>,
+    }
+
+    assert_schema_eq!(
+        Skippable,
+        r#"
+        {
+            "name": "Skippable",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "skip": { "type": "integer" },
+                    "note": { "type": ["string", "null"] },
+                    "status": { "enum": ["active", "idle"], "type": "string" }
+                },
+                "required": ["skip", "note", "status"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_allows_additional_properties_when_asked() {
+    #[derive(Deserialize, OpenAISchema)]
+    #[openai_schema(additional_properties)]
+    /// Anything else the model wants to add is allowed.
+    struct Open {
+        title: String,
+    }
+
+    assert_schema_eq!(
+        Open,
+        r#"
+        {
+            "name": "Open",
+            "description": "Anything else the model wants to add is allowed.",
+            "strict": false,
+            "schema": {
+                "type": "object",
+                "additionalProperties": true,
+                "properties": {
+                    "title": { "type": "string" }
+                },
+                "required": ["title"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_applies_enum_level_rename_all() {
+    #[derive(Deserialize, OpenAISchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Snake {
+        FirstOption,
+        SecondHTTPOption,
+        #[serde(rename = "custom")]
+        Overridden,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Screaming {
+        FirstOption,
+        Second,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    #[serde(rename_all(serialize = "lowercase", deserialize = "kebab-case"))]
+    enum Kebab {
+        FirstOption,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Renamed {
+        snake: Snake,
+        screaming: Screaming,
+        kebab: Kebab,
+    }
+
+    assert_schema_eq!(
+        Renamed,
+        r#"
+        {
+            "name": "Renamed",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "snake": {
+                        "enum": ["first_option", "second_h_t_t_p_option", "custom"],
+                        "type": "string"
+                    },
+                    "screaming": { "enum": ["FIRST_OPTION", "SECOND"], "type": "string" },
+                    "kebab": { "enum": ["first-option"], "type": "string" }
+                },
+                "required": ["snake", "screaming", "kebab"]
+            }
+        }
+        "#
+    );
+
+    // the schema has to agree with what serde actually accepts
+    let renamed: Renamed = serde_json::from_str(
+        r#"{ "snake": "second_h_t_t_p_option", "screaming": "FIRST_OPTION", "kebab": "first-option" }"#,
+    )
+    .unwrap();
+    assert!(matches!(renamed.snake, Snake::SecondHTTPOption));
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_generates_nested_arrays() {
+    #[derive(Deserialize, OpenAISchema)]
+    enum Cell {
+        Empty,
+        Full,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Grid {
+        /// The values of the grid, row by row.
+        matrix: Vec<Vec<i32>>,
+        weights: Vec<Vec<f64>>,
+        /// The state of every cell, row by row.
+        cells: Vec<Vec<Cell>>,
+        flat_cells: Vec<Cell>,
+    }
+
+    assert_schema_eq!(
+        Grid,
+        r#"
+        {
+            "name": "Grid",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "matrix": {
+                        "description": "The values of the grid, row by row.",
+                        "type": "array",
+                        "items": { "type": "array", "items": { "type": "integer" } }
+                    },
+                    "weights": {
+                        "type": "array",
+                        "items": { "type": "array", "items": { "type": "number" } }
+                    },
+                    "cells": {
+                        "description": "The state of every cell, row by row.",
+                        "type": "array",
+                        "items": {
+                            "type": "array",
+                            "items": { "enum": ["Empty", "Full"], "type": "string" }
+                        }
+                    },
+                    "flat_cells": {
+                        "type": "array",
+                        "items": { "enum": ["Empty", "Full"], "type": "string" }
+                    }
+                },
+                "required": ["matrix", "weights", "cells", "flat_cells"]
+            }
+        }
+        "#
+    );
+
+    let grid: Grid = serde_json::from_str(
+        r#"{ "matrix": [[1, 2], [3]], "weights": [[0.5]], "cells": [["Empty"], ["Full", "Empty"]], "flat_cells": [] }"#,
+    )
+    .unwrap();
+    assert_eq!(grid.matrix, vec![vec![1, 2], vec![3]]);
+    assert!(matches!(grid.cells[1][0], Cell::Full));
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_generates_nullable_arrays() {
+    #[derive(Deserialize, OpenAISchema, Debug, PartialEq)]
+    enum Tag {
+        Urgent,
+        Later,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Ticket {
+        /// Free-form labels, if there are any.
+        labels: Option<Vec<String>>,
+        /// The tags that apply, if there are any.
+        tags: Option<Vec<Tag>>,
+        priority: Option<Tag>,
+    }
+
+    assert_schema_eq!(
+        Ticket,
+        r#"
+        {
+            "name": "Ticket",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "labels": {
+                        "description": "Free-form labels, if there are any.",
+                        "type": ["array", "null"],
+                        "items": { "type": "string" }
+                    },
+                    "tags": {
+                        "description": "The tags that apply, if there are any.",
+                        "type": ["array", "null"],
+                        "items": { "enum": ["Urgent", "Later"], "type": "string" }
+                    },
+                    "priority": {
+                        "anyOf": [
+                            { "enum": ["Urgent", "Later"], "type": "string" },
+                            { "type": "null" }
+                        ]
+                    }
+                },
+                "required": ["labels", "tags", "priority"]
+            }
+        }
+        "#
+    );
+
+    let ticket: Ticket =
+        serde_json::from_str(r#"{ "labels": null, "tags": ["Urgent"], "priority": null }"#)
+            .unwrap();
+    assert_eq!(ticket.labels, None);
+    assert_eq!(ticket.tags, Some(vec![Tag::Urgent]));
+    assert_eq!(ticket.priority, None);
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_generates_fixed_size_arrays() {
+    #[derive(Deserialize, OpenAISchema)]
+    enum Axis {
+        X,
+        Y,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Swatch {
+        /// The red, green, and blue channels, from 0 to 1.
+        rgb: [f32; 3],
+        axes: [Axis; 2],
+        corners: Option<[[i32; 2]; 4]>,
+    }
+
+    assert_schema_eq!(
+        Swatch,
+        r#"
+        {
+            "name": "Swatch",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "rgb": {
+                        "description": "The red, green, and blue channels, from 0 to 1.",
+                        "type": "array",
+                        "items": { "type": "number" },
+                        "minItems": 3,
+                        "maxItems": 3
+                    },
+                    "axes": {
+                        "type": "array",
+                        "items": { "enum": ["X", "Y"], "type": "string" },
+                        "minItems": 2,
+                        "maxItems": 2
+                    },
+                    "corners": {
+                        "type": ["array", "null"],
+                        "items": {
+                            "type": "array",
+                            "items": { "type": "integer" },
+                            "minItems": 2,
+                            "maxItems": 2
+                        },
+                        "minItems": 4,
+                        "maxItems": 4
+                    }
+                },
+                "required": ["rgb", "axes", "corners"]
+            }
+        }
+        "#
+    );
+
+    let swatch: Swatch =
+        serde_json::from_str(r#"{ "rgb": [1.0, 0.5, 0.0], "axes": ["Y", "X"], "corners": null }"#)
+            .unwrap();
+    assert_eq!(swatch.rgb, [1.0, 0.5, 0.0]);
+}
+
+#[test]
+fn it_uses_the_overridden_wire_type_of_custom_serialized_fields() {
+    use std::time::{Duration, SystemTime};
+
+    mod epoch_seconds {
+        use std::time::{Duration, SystemTime};
+
+        use serde::{Deserialize, Deserializer};
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<SystemTime, D::Error> {
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::deserialize(deserializer)?))
+        }
+
+        pub mod option {
+            use super::*;
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Option<SystemTime>, D::Error> {
+                Ok(Option::<u64>::deserialize(deserializer)?
+                    .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+            }
+        }
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Event {
+        /// When the event happened, in seconds since the unix epoch.
+        #[serde(with = "epoch_seconds")]
+        #[openai_schema(as = "integer")]
+        happened_at: SystemTime,
+        #[serde(with = "epoch_seconds::option")]
+        #[openai_schema(as = "integer")]
+        ended_at: Option<SystemTime>,
+    }
+
+    assert_schema_eq!(
+        Event,
+        r#"
+        {
+            "name": "Event",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "happened_at": {
+                        "description": "When the event happened, in seconds since the unix epoch.",
+                        "type": "integer"
+                    },
+                    "ended_at": { "type": ["integer", "null"] }
+                },
+                "required": ["happened_at", "ended_at"]
+            }
+        }
+        "#
+    );
+
+    let event: Event =
+        serde_json::from_str(r#"{ "happened_at": 1727000000, "ended_at": null }"#).unwrap();
+    assert_eq!(
+        event.happened_at,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1727000000)
+    );
+    assert!(event.ended_at.is_none());
+}
+
+#[test]
+fn it_notes_field_defaults_in_their_descriptions() {
+    #[allow(dead_code)]
+    #[derive(Deserialize, OpenAISchema)]
+    struct Contact {
+        /// The contact's preferred language.
+        #[openai_schema(default = "unknown")]
+        language: String,
+        #[openai_schema(default = "0")]
+        age: u32,
+        name: String,
+    }
+
+    assert_schema_eq!(
+        Contact,
+        r#"
+        {
+            "name": "Contact",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "language": {
+                        "description": "The contact's preferred language. (default: unknown)",
+                        "type": "string"
+                    },
+                    "age": { "description": "(default: 0)", "type": "integer" },
+                    "name": { "type": "string" }
+                },
+                "required": ["language", "age", "name"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+fn it_notes_defaults_on_arrays_of_subordinate_types() {
+    #[derive(Deserialize, OpenAISchema)]
+    enum Tag {
+        Urgent,
+        Billing,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, OpenAISchema)]
+    struct Ticket {
+        #[openai_schema(default = "none")]
+        tags: Vec<Tag>,
+    }
+
+    assert_schema_eq!(
+        Ticket,
+        r#"
+        {
+            "name": "Ticket",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "tags": {
+                        "description": "(default: none)",
+                        "type": "array",
+                        "items": { "type": "string", "enum": ["Urgent", "Billing"] }
+                    }
+                },
+                "required": ["tags"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+fn it_generates_untagged_enums_as_any_of_their_variants() {
+    #[derive(Deserialize, OpenAISchema, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        Text(String),
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct Measurement {
+        value: Value,
+        alternatives: Vec<Value>,
+    }
+
+    assert_schema_eq!(
+        Measurement,
+        r#"
+        {
+            "name": "Measurement",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "value": {
+                        "anyOf": [{ "type": "number" }, { "type": "string" }]
+                    },
+                    "alternatives": {
+                        "type": "array",
+                        "items": {
+                            "anyOf": [{ "type": "number" }, { "type": "string" }]
+                        }
+                    }
+                },
+                "required": ["value", "alternatives"]
+            }
+        }
+        "#
+    );
+
+    let measurement: Measurement =
+        serde_json::from_str(r#"{ "value": 1.5, "alternatives": ["about one and a half", 2] }"#)
+            .unwrap();
+    assert_eq!(measurement.value, Value::Number(1.5));
     assert_eq!(
-        SuperComplexSchema::openai_schema().to_string(),
-        String::from(
-            r#"{"name":"SuperComplexSchema","description":"Hello friends","strict":true,"schema":{"type":"object","additionalProperties":false,"properties":{"optional_string":{"description":"The first one.","type":["string","null"]},"regular_string":{"type":"string"},"int":{"type":"integer"},"basic_enum":{"enum":["variant1","Variant2","this-has-a-really-really-long-name-that-strangely-broke-some-thing-with-this-maybe-the-attrs-have-a-max-len-i-dont-really-know"],"type":"string"}},"required":["optional_string","regular_string","int","basic_enum"]}}"#
-        )
+        measurement.alternatives,
+        vec![
+            Value::Text("about one and a half".to_owned()),
+            Value::Number(2.0)
+        ]
+    );
+}
+
+#[test]
+fn it_leniently_deserializes_repr_enums_from_numeric_strings() {
+    #[derive(serde_repr::Deserialize_repr, OpenAISchema, Debug, PartialEq)]
+    #[repr(u8)]
+    enum NicenessScore {
+        One = 1,
+        Five = 5,
+    }
+
+    #[derive(Deserialize, OpenAISchema, Debug)]
+    struct NicenessScoreContainer {
+        #[serde(deserialize_with = "kind_openai_schema::de::lenient_repr")]
+        niceness_score: NicenessScore,
+    }
+
+    let quoted: NicenessScoreContainer =
+        serde_json::from_str(r#"{ "niceness_score": "5" }"#).unwrap();
+    assert_eq!(quoted.niceness_score, NicenessScore::Five);
+
+    let unquoted: NicenessScoreContainer =
+        serde_json::from_str(r#"{ "niceness_score": 1 }"#).unwrap();
+    assert_eq!(unquoted.niceness_score, NicenessScore::One);
+
+    assert!(
+        serde_json::from_str::<NicenessScoreContainer>(r#"{ "niceness_score": "3" }"#).is_err()
+    );
+    assert!(
+        serde_json::from_str::<NicenessScoreContainer>(r#"{ "niceness_score": "five" }"#).is_err()
+    );
+
+    assert_schema_eq!(
+        NicenessScoreContainer,
+        r#"
+        {
+            "name": "NicenessScoreContainer",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "niceness_score": { "enum": [1, 5], "type": "number" }
+                },
+                "required": ["niceness_score"]
+            }
+        }
+        "#
+    );
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_scopes_rename_all_to_the_type_it_is_placed_on() {
+    #[derive(Deserialize, OpenAISchema)]
+    #[serde(rename_all = "snake_case", rename_all_fields = "camelCase")]
+    enum SentimentKind {
+        VeryPositive,
+        #[serde(rename = "meh")]
+        Neutral,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    enum Language {
+        BritishEnglish,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    #[serde(rename_all = "camelCase")]
+    struct SentimentReport {
+        sentiment_kind: SentimentKind,
+        detected_language: Language,
+        #[serde(rename = "score_out_of_ten")]
+        score: u32,
+    }
+
+    assert_schema_eq!(
+        SentimentReport,
+        r#"
+        {
+            "name": "SentimentReport",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "sentimentKind": { "enum": ["very_positive", "meh"], "type": "string" },
+                    "detectedLanguage": { "enum": ["BritishEnglish"], "type": "string" },
+                    "score_out_of_ten": { "type": "integer" }
+                },
+                "required": ["sentimentKind", "detectedLanguage", "score_out_of_ten"]
+            }
+        }
+        "#
+    );
+
+    let report: SentimentReport = serde_json::from_str(
+        r#"{ "sentimentKind": "meh", "detectedLanguage": "BritishEnglish", "score_out_of_ten": 5 }"#,
+    )
+    .unwrap();
+    assert!(matches!(report.sentiment_kind, SentimentKind::Neutral));
+}
+
+#[test]
+#[allow(dead_code)]
+fn it_embeds_schemas_without_subordinate_types_as_a_single_literal() {
+    #[derive(Deserialize, OpenAISchema)]
+    /// Only primitives.
+    struct Primitives {
+        name: String,
+        age: Option<u32>,
+        scores: Vec<f64>,
+        active: bool,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    #[derive(Deserialize, OpenAISchema)]
+    struct WithColor {
+        color: Color,
+    }
+
+    // a static schema is the same string every time, and so is a built one once it's cached
+    assert!(std::ptr::eq(
+        Primitives::openai_schema().as_str(),
+        Primitives::openai_schema().as_str()
+    ));
+    assert!(std::ptr::eq(
+        WithColor::openai_schema().as_str(),
+        WithColor::openai_schema().as_str()
+    ));
+
+    assert_schema_eq!(
+        Primitives,
+        r#"{
+            "name": "Primitives",
+            "description": "Only primitives.",
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "name": { "type": "string" },
+                    "age": { "type": ["integer", "null"] },
+                    "scores": { "type": "array", "items": { "type": "number" } },
+                    "active": { "type": "boolean" }
+                },
+                "required": ["name", "age", "scores", "active"]
+            }
+        }"#
+    );
+    assert_schema_eq!(
+        WithColor,
+        r#"{
+            "name": "WithColor",
+            "description": null,
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": { "color": { "enum": ["Red", "Blue"], "type": "string" } },
+                "required": ["color"]
+            }
+        }"#
     );
 }
@@ -0,0 +1,50 @@
+#![cfg(feature = "gzip")]
+
+mod common;
+
+use common::TestAuth;
+use kind_openai::{endpoints::files::ListFiles, OpenAI};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// Sends a single request with the given compression setting, giving the `Accept-Encoding` header it was
+/// sent with.
+async fn accept_encoding(compression: bool) -> Option<String> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": [],
+            "has_more": false,
+        })))
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .compression(compression)
+        .build()
+        .unwrap();
+    client.req(&ListFiles::new()).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    requests[0]
+        .headers
+        .get("accept-encoding")
+        .map(|value| value.to_str().unwrap().to_owned())
+}
+
+#[tokio::test]
+async fn it_asks_for_gzip_when_compression_is_enabled() {
+    assert!(accept_encoding(true).await.unwrap().contains("gzip"));
+}
+
+#[tokio::test]
+async fn it_does_not_ask_for_gzip_when_compression_is_disabled() {
+    assert!(!accept_encoding(false)
+        .await
+        .is_some_and(|encoding| encoding.contains("gzip")));
+}
@@ -0,0 +1,490 @@
+mod common;
+
+use kind_openai::endpoints::embeddings::{Embeddings, EmbeddingsModel, EmbeddingsResponse};
+
+fn response(embedding: &[f32]) -> EmbeddingsResponse {
+    serde_json::from_value(serde_json::json!({ "data": [{ "embedding": embedding }] })).unwrap()
+}
+
+fn l2_norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+#[test]
+fn it_normalizes_embeddings_to_unit_length() {
+    let embedding = response(&[3.0, 4.0, 12.0]).embedding_normalized();
+
+    assert!((l2_norm(&embedding) - 1.0).abs() < 1e-6);
+    assert!((embedding[0] - 3.0 / 13.0).abs() < 1e-6);
+}
+
+#[test]
+fn it_normalizes_embeddings_in_place() {
+    let mut response = response(&[0.5, 0.5, 0.5, 0.4]);
+    response.normalize();
+
+    assert!((l2_norm(response.embedding_ref()) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn it_leaves_zero_embeddings_untouched() {
+    assert_eq!(response(&[0.0, 0.0]).embedding_normalized(), vec![0.0, 0.0]);
+}
+
+#[test]
+fn it_serializes_the_user_and_dimensions() {
+    let request = Embeddings::model(EmbeddingsModel::TextEmbedding3Large)
+        .input("Hello!")
+        .dimensions(256)
+        .user("user-1234")
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({
+            "model": "text-embedding-3-large",
+            "input": "Hello!",
+            "dimensions": 256,
+            "user": "user-1234",
+        })
+    );
+}
+
+#[test]
+fn it_deserializes_users_containing_escapes() {
+    let request: Embeddings = serde_json::from_str(
+        r#"{ "model": "text-embedding-3-large", "input": "Hello!", "user": "team \"a\"" }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap()["user"],
+        "team \"a\""
+    );
+}
+
+#[test]
+fn it_omits_the_user_and_dimensions_when_unset() {
+    let request = Embeddings::model(EmbeddingsModel::TextEmbedding3Large)
+        .input("Hello!")
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({ "model": "text-embedding-3-large", "input": "Hello!" })
+    );
+}
+
+#[test]
+fn it_accepts_owned_inputs() {
+    fn request_for(id: u32) -> Embeddings<'static> {
+        Embeddings::model(EmbeddingsModel::TextEmbedding3Large)
+            .input(format!("Document #{id}"))
+            .build()
+    }
+
+    assert_eq!(
+        serde_json::to_value(request_for(7)).unwrap(),
+        serde_json::json!({ "model": "text-embedding-3-large", "input": "Document #7" })
+    );
+}
+
+#[test]
+fn it_truncates_inputs_over_the_token_budget() {
+    use kind_openai::endpoints::embeddings::truncate_input;
+
+    let mut short = "Hello!".to_owned();
+    assert!(!truncate_input(&mut short, 2));
+    assert_eq!(short, "Hello!");
+
+    let mut long = "Hello, world!".to_owned();
+    assert!(truncate_input(&mut long, 2));
+    assert_eq!(long, "Hello, w");
+
+    // never splits a multi-byte character
+    let mut accented = "aéé".to_owned();
+    assert!(truncate_input(&mut accented, 1));
+    assert_eq!(accented, "aé");
+}
+
+mod embed_all {
+    use std::{sync::Mutex, time::Duration};
+
+    use kind_openai::{
+        endpoints::embeddings::{embed_all, EmbeddingsModel},
+        OpenAI, OpenAIError,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, Request, Respond, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    // embeds each input (which is a number) as a single element vector of itself, in reverse order,
+    // with earlier batches taking longer to respond than later ones.
+    struct EchoEmbeddings;
+
+    impl Respond for EchoEmbeddings {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let inputs = body["input"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|input| input.as_str().unwrap().parse::<f32>().unwrap())
+                .collect::<Vec<_>>();
+
+            let data = inputs
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(index, input)| serde_json::json!({ "index": index, "embedding": [input] }))
+                .collect::<Vec<_>>();
+
+            ResponseTemplate::new(200)
+                .set_delay(Duration::from_millis(100 - inputs[0] as u64 * 10))
+                .set_body_json(serde_json::json!({
+                    "data": data,
+                    "usage": { "prompt_tokens": inputs.len(), "total_tokens": inputs.len() }
+                }))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_preserves_input_order_across_batches() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(EchoEmbeddings)
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let inputs = (0..10).map(|i| i.to_string());
+
+        let embeddings = embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs)
+            .batch_size(3)
+            .concurrency(4)
+            .call()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            embeddings.embeddings(),
+            (0..10).map(|i| vec![i as f32]).collect::<Vec<_>>()
+        );
+        assert!(embeddings.truncated().is_empty());
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn it_reports_progress_as_each_batch_completes() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(EchoEmbeddings)
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let inputs = (0..10).map(|i| i.to_string());
+        let reports = Mutex::new(Vec::new());
+
+        embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs)
+            .batch_size(3)
+            .concurrency(4)
+            .on_progress(&|progress| reports.lock().unwrap().push(progress))
+            .call()
+            .await
+            .unwrap();
+
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), 4);
+        for (i, progress) in reports.iter().enumerate() {
+            assert_eq!(progress.completed_batches, i + 1);
+            assert_eq!(progress.total_batches, 4);
+            assert_eq!(progress.total_inputs, 10);
+            assert_eq!(
+                progress.usage.total_tokens as usize,
+                progress.embedded_inputs
+            );
+        }
+        // batches can complete in any order, so only the sizes of the batches (three of three inputs and the
+        // one left over) are certain, not which completed when
+        let mut batch_sizes = reports
+            .iter()
+            .scan(0, |embedded, progress| {
+                let batch_size = progress.embedded_inputs - *embedded;
+                *embedded = progress.embedded_inputs;
+                Some(batch_size)
+            })
+            .collect::<Vec<_>>();
+        batch_sizes.sort_unstable();
+        assert_eq!(batch_sizes, [1, 3, 3, 3]);
+        assert_eq!(reports[3].embedded_inputs, 10);
+        assert_eq!(reports[3].usage.prompt_tokens, 10);
+    }
+
+    #[tokio::test]
+    async fn it_splits_batches_by_estimated_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(EchoEmbeddings)
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        // each input is estimated at one token
+        let inputs = (0..4).map(|i| i.to_string());
+
+        let embeddings = embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs)
+            .max_batch_tokens(2)
+            .call()
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings.embeddings().len(), 4);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_truncates_oversized_inputs_rather_than_failing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [
+                    { "index": 0, "embedding": [0.0] },
+                    { "index": 1, "embedding": [1.0] }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let inputs = ["1".to_owned(), "2".repeat(100)];
+
+        let embeddings = embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs)
+            .max_input_tokens(8)
+            .call()
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings.truncated(), [1]);
+        assert_eq!(embeddings.into_embeddings(), vec![vec![0.0], vec![1.0]]);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["input"], serde_json::json!(["1", "2".repeat(32)]));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_a_batch_missing_embeddings() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "index": 0, "embedding": [0.0] }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let inputs = ["1".to_owned(), "2".to_owned()];
+
+        let err = embed_all(&client, EmbeddingsModel::TextEmbedding3Large, inputs)
+            .call()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            OpenAIError::EmbeddingCountMismatch {
+                expected: 2,
+                actual: 1
+            }
+        ));
+    }
+}
+
+mod embed_multi {
+    use kind_openai::{endpoints::embeddings::EmbeddingsModel, OpenAI, OpenAIError};
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    #[tokio::test]
+    async fn it_gives_each_model_its_own_result() {
+        let server = MockServer::start().await;
+        for (model, embedding) in [
+            ("text-embedding-3-large", [1.0, 0.0]),
+            ("text-embedding-3-small", [0.0, 1.0]),
+        ] {
+            Mock::given(method("POST"))
+                .and(path("/embeddings"))
+                .and(body_partial_json(
+                    serde_json::json!({ "model": model, "input": "hello" }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "data": [{ "index": 0, "embedding": embedding }]
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "text-embedding-ada-002" }),
+            ))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": "The model `text-embedding-ada-002` does not exist.",
+                    "param": null,
+                    "code": "model_not_found",
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let models = [
+            EmbeddingsModel::TextEmbedding3Large,
+            EmbeddingsModel::TextEmbedding3Small,
+            EmbeddingsModel::TextEmbeddingAda002,
+        ];
+        let results = client
+            .embed_multi("hello", &models)
+            .concurrency(2)
+            .call()
+            .await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[&EmbeddingsModel::TextEmbedding3Large]
+                .as_ref()
+                .unwrap(),
+            &[1.0, 0.0]
+        );
+        assert_eq!(
+            results[&EmbeddingsModel::TextEmbedding3Small]
+                .as_ref()
+                .unwrap(),
+            &[0.0, 1.0]
+        );
+        assert!(matches!(
+            results[&EmbeddingsModel::TextEmbeddingAda002],
+            Err(OpenAIError::API(_))
+        ));
+    }
+}
+
+mod embedding_index {
+    use kind_openai::{endpoints::embeddings::EmbeddingIndex, OpenAIError};
+
+    fn index() -> EmbeddingIndex<&'static str> {
+        let mut index = EmbeddingIndex::new();
+        index.insert("east", vec![1.0, 0.0]).unwrap();
+        index.insert("north", vec![0.0, 2.0]).unwrap();
+        index.insert("north-east", vec![3.0, 3.0]).unwrap();
+        index.insert("west", vec![-1.0, 0.0]).unwrap();
+        index
+    }
+
+    #[test]
+    fn it_searches_by_cosine_similarity() {
+        let results = index().search(&[1.0, 0.1], 3).unwrap();
+
+        let ids = results.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["east", "north-east", "north"]);
+        assert!(results[0].1 > 0.99 && results[0].1 <= 1.0 + 1e-6);
+    }
+
+    #[test]
+    fn it_ignores_magnitude() {
+        let results = index().search(&[0.0, 100.0], 1).unwrap();
+
+        assert_eq!(results[0].0, "north");
+        assert!((results[0].1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_gives_everything_when_top_k_is_larger_than_the_index() {
+        let results = index().search(&[-1.0, 0.0], 10).unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].0, "west");
+        assert_eq!(results[3].0, "east");
+        assert!((results[3].1 + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_replaces_and_removes_embeddings() {
+        let mut index = index();
+        index.insert("east", vec![0.0, -1.0]).unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.search(&[0.0, -1.0], 1).unwrap()[0].0, "east");
+
+        assert!(index.remove(&"east"));
+        assert!(!index.remove(&"east"));
+        assert_eq!(index.len(), 3);
+        assert!(index
+            .search(&[0.0, -1.0], 4)
+            .unwrap()
+            .iter()
+            .all(|(id, _)| *id != "east"));
+    }
+
+    #[test]
+    fn it_rejects_embeddings_with_mismatched_dimensions() {
+        let mut index = index();
+        assert_eq!(index.dimensions(), Some(2));
+
+        assert!(matches!(
+            index.insert("up", vec![0.0, 0.0, 1.0]),
+            Err(OpenAIError::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+        assert_eq!(index.len(), 4);
+        assert!(matches!(
+            index.search(&[1.0, 0.0, 0.0, 0.0], 1),
+            Err(OpenAIError::DimensionMismatch {
+                expected: 2,
+                actual: 4
+            })
+        ));
+
+        // an empty index accepts embeddings of any size
+        let mut index = EmbeddingIndex::new();
+        index.insert("up", vec![0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(index.dimensions(), Some(3));
+    }
+}
@@ -0,0 +1,384 @@
+mod common;
+
+use kind_openai::{OpenAIError, OpenAIResult, OpenAIResultExt, RefusalOr};
+
+#[test]
+fn it_turns_refusals_into_values() {
+    let result: OpenAIResult<String> = Err(OpenAIError::Refusal("I can't do that.".to_owned()));
+
+    assert_eq!(
+        result.allow_refusal().unwrap(),
+        RefusalOr::Refusal("I can't do that.".to_owned())
+    );
+}
+
+#[test]
+fn it_passes_through_values() {
+    let result: OpenAIResult<u32> = Ok(42);
+
+    assert_eq!(result.allow_refusal().unwrap(), RefusalOr::Value(42));
+}
+
+#[test]
+fn it_passes_through_genuine_errors() {
+    let result: OpenAIResult<u32> = Err(OpenAIError::MissingAuthToken);
+
+    assert!(matches!(
+        result.allow_refusal(),
+        Err(OpenAIError::MissingAuthToken)
+    ));
+}
+
+mod is_retryable {
+    use kind_openai::{
+        error::{OpenAIAPIError, OpenAIAPIErrorData},
+        OpenAIError,
+    };
+
+    fn api_error(ty: &str) -> OpenAIError {
+        let error: OpenAIAPIError = serde_json::from_value(serde_json::json!({
+            "type": ty,
+            "message": "something went wrong",
+            "param": null,
+            "code": null,
+        }))
+        .unwrap();
+
+        OpenAIError::API(error)
+    }
+
+    #[test]
+    fn transient_api_errors_are_retryable() {
+        for ty in [
+            "server_error",
+            "cf_bad_gateway",
+            "cf_service_unavailable",
+            "internal_error",
+        ] {
+            assert!(api_error(ty).is_retryable(), "{ty} should be retryable");
+        }
+    }
+
+    #[test]
+    fn rate_limits_are_retryable() {
+        for ty in ["requests", "tokens", "rate_limit_exceeded"] {
+            let err = api_error(ty);
+            assert!(matches!(
+                err,
+                OpenAIError::API(OpenAIAPIError::RateLimitExceeded(_))
+            ));
+            assert!(err.is_retryable(), "{ty} should be retryable");
+        }
+    }
+
+    #[test]
+    fn permanent_api_errors_are_not_retryable() {
+        for ty in [
+            "invalid_request_error",
+            "context_length_exceeded",
+            "quota_exceeded",
+        ] {
+            assert!(
+                !api_error(ty).is_retryable(),
+                "{ty} should not be retryable"
+            );
+        }
+    }
+
+    #[test]
+    fn local_errors_are_not_retryable() {
+        let serde_err = serde_json::from_str::<u32>("nope").unwrap_err();
+
+        assert!(!OpenAIError::Serde("nope".to_owned(), serde_err).is_retryable());
+        assert!(!OpenAIError::Refusal("I can't do that.".to_owned()).is_retryable());
+        assert!(!OpenAIError::Validation("bad".to_owned()).is_retryable());
+        assert!(!OpenAIError::DimensionMismatch {
+            expected: 2,
+            actual: 3
+        }
+        .is_retryable());
+        assert!(!OpenAIError::MissingAuthToken.is_retryable());
+        assert!(
+            !OpenAIError::API(OpenAIAPIError::InvalidRequestError(OpenAIAPIErrorData {
+                message: "bad".to_owned(),
+                param: None,
+                code: None,
+            }))
+            .is_retryable()
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_errors_are_retryable() {
+        let err = reqwest::get(crate::common::refused_address().await)
+            .await
+            .unwrap_err();
+
+        assert!(OpenAIError::from(err).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retryable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let err = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(50))
+            .build()
+            .unwrap()
+            .get(address)
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(err.is_timeout());
+        let err = OpenAIError::from(err);
+        assert!(err.is_timeout());
+        assert!(err.is_retryable());
+        drop(listener);
+    }
+}
+
+mod timeouts {
+    use std::time::Duration;
+
+    use kind_openai::{endpoints::files::ListFiles, OpenAI, OpenAIError};
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use crate::common::TestAuth;
+
+    #[tokio::test]
+    async fn it_gives_a_timeout_error_when_the_client_timeout_elapses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"object":"list","data":[],"has_more":false}"#)
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let err = client.req(&ListFiles::new()).await.unwrap_err();
+
+        assert!(matches!(err, OpenAIError::Timeout(_)));
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn connection_refusals_are_not_timeouts() {
+        let err = OpenAIError::from(
+            reqwest::get(crate::common::refused_address().await)
+                .await
+                .unwrap_err(),
+        );
+
+        assert!(matches!(err, OpenAIError::Reqwest(_)));
+        assert!(!err.is_timeout());
+    }
+}
+
+mod hints {
+    use kind_openai::error::OpenAIAPIError;
+
+    fn invalid_request(message: &str, param: &str, code: &str) -> OpenAIAPIError {
+        serde_json::from_value(serde_json::json!({
+            "type": "invalid_request_error",
+            "message": message,
+            "param": param,
+            "code": code,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_hints_at_sampling_parameters_sent_to_reasoning_models() {
+        let error = invalid_request(
+            "Unsupported value: 'temperature' does not support 0.5 with this model. Only the default (1) value is supported.",
+            "temperature",
+            "unsupported_value",
+        );
+
+        let OpenAIAPIError::InvalidRequestError(data) = &error else {
+            panic!("expected an invalid request error");
+        };
+        assert!(data.hint().unwrap().contains("ChatReasoningCompletion"));
+        assert!(error.to_string().contains(
+            "(hint: reasoning models (such as o1 and o3-mini) don't support `temperature`"
+        ));
+    }
+
+    #[test]
+    fn it_hints_at_the_replacement_for_max_tokens() {
+        let error = invalid_request(
+            "Unsupported parameter: 'max_tokens' is not supported with this model. Use 'max_completion_tokens' instead.",
+            "max_tokens",
+            "unsupported_parameter",
+        );
+
+        assert!(error
+            .to_string()
+            .contains("use `max_completion_tokens` instead"));
+    }
+
+    #[test]
+    fn it_does_not_hint_at_unrelated_errors() {
+        let error = invalid_request(
+            "Invalid 'messages': empty array.",
+            "messages",
+            "empty_array",
+        );
+
+        let OpenAIAPIError::InvalidRequestError(data) = &error else {
+            panic!("expected an invalid request error");
+        };
+        assert!(data.hint().is_none());
+        assert!(!error.to_string().contains("hint"));
+    }
+}
+
+mod source {
+    use std::error::Error;
+
+    use kind_openai::OpenAIError;
+
+    #[test]
+    fn serde_errors_keep_their_cause() {
+        let cause = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let message = cause.to_string();
+        let err = OpenAIError::Serde("{".to_owned(), cause);
+
+        let source = err.source().unwrap();
+        assert!(source.is::<serde_json::Error>());
+        assert_eq!(source.to_string(), message);
+        // the cause is only reported through `source`, so that error reporters don't print it twice
+        assert!(!err.to_string().contains(&message));
+    }
+
+    #[tokio::test]
+    async fn reqwest_errors_keep_their_cause() {
+        let cause = reqwest::get(crate::common::refused_address().await)
+            .await
+            .unwrap_err();
+        let message = cause.to_string();
+        let err = OpenAIError::from(cause);
+
+        assert!(matches!(err, OpenAIError::Reqwest(_)));
+        let source = err.source().unwrap();
+        assert!(source.is::<reqwest::Error>());
+        assert_eq!(source.to_string(), message);
+    }
+
+    #[test]
+    fn local_errors_have_no_cause() {
+        assert!(OpenAIError::MissingAuthToken.source().is_none());
+    }
+}
+
+mod invalid_schema {
+    use kind_openai::{
+        endpoints::chat::{ChatCompletion, Model},
+        error::OpenAIAPIError,
+        user_message, OpenAI, OpenAIError, OpenAISchema,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    #[derive(serde::Deserialize, OpenAISchema)]
+    #[allow(dead_code)]
+    struct Name {
+        name: String,
+    }
+
+    async fn send(kind: &str, param: &str, code: Option<&str>, message: &str) -> OpenAIError {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {
+                    "type": kind,
+                    "message": message,
+                    "param": param,
+                    "code": code,
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let request = ChatCompletion::model(Model::Gpt4oMini)
+            .messages(vec![user_message!("My name is Jo.")])
+            .structured::<Name>()
+            .unwrap();
+        client.req(&request).await.err().unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_recognizes_rejected_schemas() {
+        let message = "Invalid schema for response_format 'Name': In context=(), 'additionalProperties' is required to be supplied and to be false.";
+
+        match send(
+            "invalid_request_error",
+            "response_format",
+            Some("invalid_json_schema"),
+            message,
+        )
+        .await
+        {
+            OpenAIError::InvalidSchema(reason) => assert_eq!(reason, message),
+            err => panic!("expected an invalid schema error, got {err:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_leaves_other_invalid_requests_alone() {
+        let err = send(
+            "invalid_request_error",
+            "messages",
+            None,
+            "Invalid 'messages': empty array.",
+        )
+        .await;
+
+        assert!(matches!(err, OpenAIError::API(_)));
+    }
+
+    #[tokio::test]
+    async fn it_leaves_other_errors_about_the_response_format_alone() {
+        let err = send(
+            "invalid_request_error",
+            "response_format",
+            Some("unsupported_value"),
+            "Invalid parameter: 'response_format' of type 'json_schema' is not supported with this model.",
+        )
+        .await;
+        assert!(matches!(
+            err,
+            OpenAIError::API(OpenAIAPIError::InvalidRequestError(_))
+        ));
+
+        let err = send(
+            "requests",
+            "response_format",
+            Some("rate_limit_exceeded"),
+            "Rate limit reached for requests.",
+        )
+        .await;
+        assert!(matches!(
+            err,
+            OpenAIError::API(OpenAIAPIError::RateLimitExceeded(_))
+        ));
+    }
+}
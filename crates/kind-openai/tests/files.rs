@@ -0,0 +1,137 @@
+mod common;
+
+use common::TestAuth;
+use kind_openai::{
+    endpoints::files::{DeleteFile, FileList, FilePurpose, ListFiles, UploadFile},
+    OpenAI,
+};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[tokio::test]
+async fn it_uploads_files_as_a_multipart_form() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "file-abc123",
+            "object": "file",
+            "bytes": 13,
+            "created_at": 1721764800,
+            "filename": "data.jsonl",
+            "purpose": "fine-tune",
+        })))
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let file = client
+        .req(&UploadFile::new(
+            b"{\"a\": \"b\"}\n".to_vec(),
+            "data.jsonl",
+            FilePurpose::FineTune,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(file.id(), "file-abc123");
+    assert_eq!(file.purpose(), &FilePurpose::FineTune);
+
+    let request = &server.received_requests().await.unwrap()[0];
+    let content_type = request.headers["content-type"].to_str().unwrap();
+    assert!(content_type.starts_with("multipart/form-data; boundary="));
+
+    let body = String::from_utf8_lossy(&request.body);
+    assert!(body.contains("name=\"purpose\"\r\n\r\nfine-tune\r\n"));
+    assert!(body.contains("name=\"file\"; filename=\"data.jsonl\""));
+    assert!(body.contains("{\"a\": \"b\"}\n"));
+}
+
+#[test]
+fn it_deserializes_a_file_list() {
+    let list: FileList = serde_json::from_str(
+        r#"{
+            "object": "list",
+            "data": [
+                {"id": "file-1", "object": "file", "bytes": 175, "created_at": 1613677385, "filename": "train.jsonl", "purpose": "fine-tune"},
+                {"id": "file-2", "object": "file", "bytes": 140, "created_at": 1613779121, "filename": "batch.jsonl", "purpose": "batch"},
+                {"id": "file-3", "object": "file", "bytes": 12, "created_at": 1613779122, "filename": "eval.jsonl", "purpose": "evals"}
+            ],
+            "has_more": false
+        }"#,
+    )
+    .unwrap();
+
+    let files = list.files();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[0].filename(), "train.jsonl");
+    assert_eq!(files[1].purpose(), &FilePurpose::Batch);
+    assert_eq!(files[2].purpose(), &FilePurpose::Other("evals".to_owned()));
+    assert!(!list.has_more());
+}
+
+#[tokio::test]
+async fn it_lists_and_deletes_files() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": [{"id": "file-1", "object": "file", "bytes": 175, "created_at": 1613677385, "filename": "train.jsonl", "purpose": "fine-tune"}],
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/files/file-1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "file-1",
+            "object": "file",
+            "deleted": true,
+        })))
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+
+    let list = client
+        .req(&ListFiles::purpose(FilePurpose::FineTune))
+        .await
+        .unwrap();
+    assert_eq!(list.files()[0].id(), "file-1");
+
+    let deleted = client.req(&DeleteFile::new("file-1")).await.unwrap();
+    assert_eq!(deleted.id(), "file-1");
+    assert!(deleted.deleted());
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests[0].url.query(), Some("purpose=fine-tune"));
+    assert!(requests.iter().all(|request| request.body.is_empty()));
+}
+
+#[test]
+fn it_lists_files_by_purpose_with_query_params() {
+    use kind_openai::endpoints::OpenAIRequestProvider;
+
+    let request = ListFiles::purpose(FilePurpose::FineTune);
+    assert_eq!(request.path_with_leading_slash(), "/files");
+    assert_eq!(
+        request.query_params(),
+        vec![("purpose".to_owned(), "fine-tune".to_owned())]
+    );
+
+    assert!(ListFiles::new().query_params().is_empty());
+
+    // a purpose this crate doesn't know about is sent exactly as OpenAI named it
+    assert_eq!(
+        ListFiles::purpose(FilePurpose::Other("evals".to_owned())).query_params(),
+        vec![("purpose".to_owned(), "evals".to_owned())]
+    );
+}
@@ -0,0 +1,98 @@
+use kind_openai::endpoints::{
+    fine_tuning::{
+        CreateFineTuningJob, FineTuningHyperparameters, FineTuningJob, FineTuningJobStatus,
+        RetrieveFineTuningJob,
+    },
+    OpenAIRequestProvider,
+};
+
+#[test]
+fn it_deserializes_a_running_job() {
+    let job: FineTuningJob = serde_json::from_str(
+        r#"{
+            "object": "fine_tuning.job",
+            "id": "ftjob-abc123",
+            "model": "gpt-4o-mini-2024-07-18",
+            "created_at": 1721764800,
+            "finished_at": null,
+            "fine_tuned_model": null,
+            "organization_id": "org-123",
+            "result_files": [],
+            "status": "running",
+            "validation_file": null,
+            "training_file": "file-abc123",
+            "trained_tokens": null,
+            "error": null
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(job.id(), "ftjob-abc123");
+    assert_eq!(job.status(), FineTuningJobStatus::Running);
+    assert_eq!(job.fine_tuned_model(), None);
+    assert!(job.error().is_none());
+}
+
+#[test]
+fn it_deserializes_a_succeeded_job() {
+    let job: FineTuningJob = serde_json::from_str(
+        r#"{
+            "id": "ftjob-abc123",
+            "model": "gpt-4o-mini-2024-07-18",
+            "created_at": 1721764800,
+            "finished_at": 1721768400,
+            "fine_tuned_model": "ft:gpt-4o-mini-2024-07-18:org::abc123",
+            "status": "succeeded",
+            "training_file": "file-abc123",
+            "trained_tokens": 5768
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(job.status(), FineTuningJobStatus::Succeeded);
+    assert_eq!(
+        job.fine_tuned_model(),
+        Some("ft:gpt-4o-mini-2024-07-18:org::abc123")
+    );
+    assert_eq!(job.finished_at(), Some(1721768400));
+    assert_eq!(job.trained_tokens(), Some(5768));
+}
+
+#[test]
+fn it_falls_back_to_other_for_unknown_statuses() {
+    for (status, expected) in [
+        ("validating_files", FineTuningJobStatus::ValidatingFiles),
+        ("queued", FineTuningJobStatus::Queued),
+        ("failed", FineTuningJobStatus::Failed),
+        ("cancelled", FineTuningJobStatus::Cancelled),
+        ("pausing", FineTuningJobStatus::Other),
+    ] {
+        assert_eq!(
+            serde_json::from_value::<FineTuningJobStatus>(serde_json::json!(status)).unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn it_builds_fine_tuning_requests() {
+    let create = CreateFineTuningJob::model("gpt-4o-mini-2024-07-18")
+        .training_file("file-abc123")
+        .hyperparameters(FineTuningHyperparameters::builder().n_epochs(3).build())
+        .build();
+
+    assert_eq!(create.path_with_leading_slash(), "/fine_tuning/jobs");
+    assert_eq!(
+        serde_json::to_value(&create).unwrap(),
+        serde_json::json!({
+            "model": "gpt-4o-mini-2024-07-18",
+            "training_file": "file-abc123",
+            "hyperparameters": { "n_epochs": 3 },
+        })
+    );
+
+    assert_eq!(
+        RetrieveFineTuningJob::new("ftjob-abc123").path_with_leading_slash(),
+        "/fine_tuning/jobs/ftjob-abc123"
+    );
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "middleware")]
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use common::TestAuth;
+use http::Extensions;
+use kind_openai::{endpoints::files::ListFiles, OpenAI, OpenAIError};
+use reqwest::{Request, Response};
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+// counts every request and tags it with a header, to prove that requests pass through the middleware
+struct Tagging(Arc<AtomicUsize>);
+
+#[async_trait::async_trait]
+impl Middleware for Tagging {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        req.headers_mut().insert("x-tagged", "yes".parse().unwrap());
+        next.run(req, extensions).await
+    }
+}
+
+struct Refusing;
+
+#[async_trait::async_trait]
+impl Middleware for Refusing {
+    async fn handle(
+        &self,
+        _req: Request,
+        _extensions: &mut Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        Err(reqwest_middleware::Error::middleware(
+            std::io::Error::other("refused by middleware"),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn it_sends_requests_through_the_middleware() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .and(header("x-tagged", "yes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": [],
+        })))
+        .mount(&server)
+        .await;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap()
+        .with_middleware_client(
+            ClientBuilder::new(reqwest::Client::new())
+                .with(Tagging(count.clone()))
+                .build(),
+        );
+
+    let files = client.req(&ListFiles::new()).await.unwrap();
+
+    assert!(files.files().is_empty());
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn it_surfaces_middleware_errors() {
+    let client = OpenAI::builder(TestAuth)
+        .base_url(common::refused_address().await)
+        .build()
+        .unwrap()
+        .with_middleware_client(
+            ClientBuilder::new(reqwest::Client::new())
+                .with(Refusing)
+                .build(),
+        );
+
+    let err = client.req(&ListFiles::new()).await.unwrap_err();
+
+    assert!(matches!(err, OpenAIError::Middleware(_)));
+    assert!(!err.is_retryable());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::TestAuth;
+use kind_openai::{
+    endpoints::{
+        chat::{ChatCompletion, Model},
+        moderations::{Moderation, ModerationModel},
+    },
+    user_message, OpenAI, OpenAIError,
+};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn moderation_response(flagged: bool, violence_score: f64) -> serde_json::Value {
+    serde_json::json!({
+        "id": "modr-123",
+        "model": "omni-moderation-2024-09-26",
+        "results": [{
+            "flagged": flagged,
+            "categories": { "harassment": false, "violence": flagged },
+            "category_scores": { "harassment": 0.001, "violence": violence_score }
+        }]
+    })
+}
+
+async fn server(moderation: serde_json::Value, expected_completions: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/moderations"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(moderation))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "created": 1727000000,
+            "model": "gpt-4o-mini-2024-07-18",
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": { "role": "assistant", "content": "Hi!", "refusal": null }
+            }]
+        })))
+        .expect(expected_completions)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn request() -> ChatCompletion<'static> {
+    ChatCompletion::model(Model::Gpt4oMini)
+        .messages(vec![user_message!("Hello!")])
+        .unstructured()
+        .unwrap()
+}
+
+#[test]
+fn it_serializes_moderation_requests() {
+    let moderation = Moderation::input("Hello!")
+        .model(ModerationModel::OmniModerationLatest)
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&moderation).unwrap(),
+        serde_json::json!({ "input": "Hello!", "model": "omni-moderation-latest" })
+    );
+}
+
+#[tokio::test]
+async fn it_short_circuits_flagged_input() {
+    let server = server(moderation_response(true, 0.97), 0).await;
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+
+    let result = client
+        .screened_req(&Moderation::input("Hello!").build(), &request())
+        .call()
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(OpenAIError::Flagged(categories)) if categories == ["violence"]
+    ));
+}
+
+#[tokio::test]
+async fn it_sends_the_request_when_nothing_is_flagged() {
+    let server = server(moderation_response(false, 0.2), 1).await;
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+
+    let response = client
+        .screened_req(&Moderation::input("Hello!").build(), &request())
+        .call()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.take_first_choice().unwrap().message().unwrap(),
+        "Hi!"
+    );
+}
+
+#[tokio::test]
+async fn it_flags_categories_at_or_above_the_threshold() {
+    let server = server(moderation_response(false, 0.2), 0).await;
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+
+    let result = client
+        .screened_req(&Moderation::input("Hello!").build(), &request())
+        .threshold(0.2)
+        .call()
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(OpenAIError::Flagged(categories)) if categories == ["violence"]
+    ));
+}
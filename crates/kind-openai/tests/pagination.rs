@@ -0,0 +1,173 @@
+mod common;
+
+use common::TestAuth;
+use futures_util::StreamExt;
+use kind_openai::{
+    endpoints::files::{FilePurpose, ListFiles},
+    OpenAI,
+};
+use wiremock::{
+    matchers::{method, path, query_param, query_param_is_missing},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn file(id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "object": "file",
+        "bytes": 120,
+        "created_at": 1727000000,
+        "filename": format!("{id}.jsonl"),
+        "purpose": "fine-tune"
+    })
+}
+
+fn page(ids: &[&str], has_more: bool) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "object": "list",
+        "data": ids.iter().map(|id| file(id)).collect::<Vec<_>>(),
+        "first_id": ids.first(),
+        "last_id": ids.last(),
+        "has_more": has_more
+    }))
+}
+
+#[tokio::test]
+async fn it_pages_through_every_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .and(query_param_is_missing("after"))
+        .respond_with(page(&["file-1", "file-2"], true))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .and(query_param("after", "file-2"))
+        // the original filters are kept on subsequent pages
+        .and(query_param("purpose", "fine-tune"))
+        .respond_with(page(&["file-3"], false))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let request = ListFiles::purpose(FilePurpose::FineTune);
+
+    let ids = client
+        .paginate(&request)
+        .map(|file| file.unwrap().id().to_owned())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids, vec!["file-1", "file-2", "file-3"]);
+}
+
+#[tokio::test]
+async fn it_stops_after_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .and(query_param_is_missing("after"))
+        .respond_with(page(&["file-1"], true))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/files"))
+        .and(query_param("after", "file-1"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(serde_json::json!({
+            "error": { "message": "The server had an error.", "type": "server_error", "code": null }
+        })))
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let request = ListFiles::new();
+
+    let results = client.paginate(&request).collect::<Vec<_>>().await;
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().id(), "file-1");
+    assert!(results[1].is_err());
+}
+
+#[tokio::test]
+async fn it_replaces_the_starting_cursor_of_the_request() {
+    use kind_openai::endpoints::chat::ListStoredCompletions;
+
+    let completion = |id: &str| {
+        serde_json::json!({
+            "object": "chat.completion",
+            "id": id,
+            "model": "gpt-4o-mini-2024-07-18",
+            "created": 1738960610,
+            "choices": [{
+                "index": 0,
+                "message": { "content": "Hi!", "role": "assistant", "refusal": null },
+                "finish_reason": "stop",
+                "logprobs": null
+            }]
+        })
+    };
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/chat/completions"))
+        .and(query_param("after", "chatcmpl-0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": [completion("chatcmpl-1")],
+            "first_id": "chatcmpl-1",
+            "last_id": "chatcmpl-1",
+            "has_more": true
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/chat/completions"))
+        .and(query_param("after", "chatcmpl-1"))
+        .and(query_param("model", "gpt-4o-mini"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": [completion("chatcmpl-2")],
+            "first_id": "chatcmpl-2",
+            "last_id": "chatcmpl-2",
+            "has_more": false
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let request = ListStoredCompletions::builder()
+        .model("gpt-4o-mini")
+        .after("chatcmpl-0")
+        .build();
+
+    let ids = client
+        .paginate(&request)
+        .map(|completion| completion.unwrap().id().to_owned())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(ids, vec!["chatcmpl-1", "chatcmpl-2"]);
+
+    // the cursor is only ever sent once
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|request| request
+        .url
+        .query_pairs()
+        .filter(|(key, _)| key == "after")
+        .count()
+        == 1));
+}
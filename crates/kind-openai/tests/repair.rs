@@ -0,0 +1,115 @@
+mod common;
+
+use common::TestAuth;
+use kind_openai::{
+    endpoints::chat::{req_with_repair, ChatCompletion, Model},
+    system_message, user_message, OpenAI, OpenAIError, OpenAISchema,
+};
+use serde::Deserialize;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[derive(Deserialize, OpenAISchema, Debug)]
+struct Name {
+    name: String,
+}
+
+fn completion(content: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion",
+        "created": 1727000000,
+        "model": "gpt-4o-mini-2024-07-18",
+        "choices": [{
+            "index": 0,
+            "finish_reason": "stop",
+            "message": { "role": "assistant", "content": content, "refusal": null },
+            "logprobs": null
+        }],
+        "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+    }))
+}
+
+async fn mount_responses(server: &MockServer, contents: &[&str]) {
+    for content in contents {
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(completion(content))
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+    }
+}
+
+fn request() -> kind_openai::endpoints::chat::StructuredChatCompletion<'static, Name> {
+    ChatCompletion::model(Model::Gpt4oMini)
+        .messages(vec![
+            system_message!("Extract the name."),
+            user_message!("Hi, I'm John."),
+        ])
+        .structured::<Name>()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn it_repairs_output_that_fails_to_deserialize() {
+    let server = MockServer::start().await;
+    mount_responses(
+        &server,
+        &[
+            r#"{"name": "John"} I hope this helps!"#,
+            r#"{"name": "John"}"#,
+        ],
+    )
+    .await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let name = req_with_repair(&client, &request())
+        .repair_prompt("Fix it: {error}")
+        .call()
+        .await
+        .unwrap();
+
+    assert_eq!(name.name, "John");
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+
+    let repair: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+    let messages = repair["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[2]["role"], "assistant");
+    assert_eq!(
+        messages[2]["content"],
+        r#"{"name": "John"} I hope this helps!"#
+    );
+    assert_eq!(messages[3]["role"], "user");
+    assert!(messages[3]["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("Fix it: trailing characters"));
+    assert_eq!(repair["response_format"]["json_schema"]["name"], "Name");
+}
+
+#[tokio::test]
+async fn it_gives_up_after_the_maximum_number_of_repairs() {
+    let server = MockServer::start().await;
+    mount_responses(&server, &["not json", "still not json", "never json"]).await;
+
+    let client = OpenAI::builder(TestAuth)
+        .base_url(server.uri())
+        .build()
+        .unwrap();
+    let result = req_with_repair(&client, &request())
+        .max_repairs(2)
+        .call()
+        .await;
+
+    assert!(matches!(result, Err(OpenAIError::Serde(content, _)) if content == "never json"));
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
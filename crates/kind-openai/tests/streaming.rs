@@ -0,0 +1,301 @@
+mod common;
+
+use kind_openai::{
+    endpoints::chat::{ChatCompletionChunk, StructuredChatCompletionAccumulator},
+    OpenAIError, OpenAISchema,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, OpenAISchema, Debug, PartialEq)]
+struct Name {
+    first_name: String,
+    last_name: Option<String>,
+}
+
+fn content_chunk(content: &str) -> ChatCompletionChunk {
+    serde_json::from_value(serde_json::json!({
+        "choices": [{ "index": 0, "delta": { "content": content }, "finish_reason": null }],
+        "usage": null,
+    }))
+    .unwrap()
+}
+
+fn refusal_chunk(refusal: &str) -> ChatCompletionChunk {
+    serde_json::from_value(serde_json::json!({
+        "choices": [{ "index": 0, "delta": { "refusal": refusal }, "finish_reason": null }],
+        "usage": null,
+    }))
+    .unwrap()
+}
+
+fn final_chunk() -> ChatCompletionChunk {
+    serde_json::from_str(
+        r#"{"choices":[],"usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15}}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn it_accumulates_fragmented_structured_json() {
+    let mut accumulator = StructuredChatCompletionAccumulator::<Name>::new();
+
+    let fragments = ["{\"first", "_name\":\"Jo", "hn\",\"last_name\"", ":null}"];
+    for fragment in fragments {
+        assert!(accumulator.push(&content_chunk(fragment)));
+    }
+    assert!(!accumulator.push(&final_chunk()));
+
+    assert_eq!(
+        accumulator.snapshot(),
+        r#"{"first_name":"John","last_name":null}"#
+    );
+    assert_eq!(accumulator.usage().unwrap().total_tokens, 15);
+    assert_eq!(
+        accumulator.finish().unwrap(),
+        Name {
+            first_name: "John".to_owned(),
+            last_name: None,
+        }
+    );
+}
+
+#[test]
+fn it_surfaces_refusals_streamed_mid_response() {
+    let mut accumulator = StructuredChatCompletionAccumulator::<Name>::new();
+
+    accumulator.push(&content_chunk(""));
+    accumulator.push(&refusal_chunk("I'm sorry, "));
+    accumulator.push(&refusal_chunk("I can't help with that."));
+    accumulator.push(&final_chunk());
+
+    assert_eq!(
+        accumulator.refusal(),
+        Some("I'm sorry, I can't help with that.")
+    );
+    match accumulator.finish() {
+        Err(OpenAIError::Refusal(refusal)) => {
+            assert_eq!(refusal, "I'm sorry, I can't help with that.")
+        }
+        _ => panic!("expected a refusal"),
+    }
+}
+
+mod dropping {
+    use kind_openai::{
+        endpoints::chat::{ChatCompletion, Model},
+        user_message, OpenAI,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    async fn counting_server() -> MockServer {
+        let body = (0..100)
+            .map(|i| {
+                format!(
+                    "data: {{\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"{i} \"}},\"finish_reason\":null}}],\"usage\":null}}\n\n"
+                )
+            })
+            .chain(["data: [DONE]\n\n".to_owned()])
+            .collect::<String>();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn request() -> ChatCompletion<'static> {
+        ChatCompletion::model(Model::Gpt4oMini)
+            .messages(vec![user_message!("Count to 100.")])
+            .unstructured()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_can_drop_a_stream_early_and_keep_using_the_client() {
+        let server = counting_server().await;
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let request = request();
+
+        let mut abandoned = client.stream(&request).await.unwrap();
+        let first = abandoned.next().await.unwrap().unwrap();
+        assert_eq!(first.choices()[0].delta().content(), Some("0 "));
+        drop(abandoned);
+
+        let mut stream = client.stream(&request).await.unwrap();
+        let mut content = String::new();
+        while let Some(chunk) = stream.next().await {
+            content.push_str(chunk.unwrap().choices()[0].delta().content().unwrap());
+        }
+        assert_eq!(
+            content,
+            (0..100).map(|i| format!("{i} ")).collect::<String>()
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn it_warns_about_dropped_streams_only() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // counts the warnings the crate emits, which is all that a full subscriber would be needed for
+        struct CountWarnings(Arc<AtomicUsize>);
+
+        impl tracing::Subscriber for CountWarnings {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                tracing::span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+            fn event(&self, event: &tracing::Event<'_>) {
+                let metadata = event.metadata();
+                if *metadata.level() == tracing::Level::WARN
+                    && metadata.target().starts_with("kind_openai")
+                {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            fn enter(&self, _: &tracing::span::Id) {}
+
+            fn exit(&self, _: &tracing::span::Id) {}
+        }
+
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let _guard = tracing::subscriber::set_default(CountWarnings(warnings.clone()));
+
+        let server = counting_server().await;
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let request = request();
+
+        let mut finished = client.stream(&request).await.unwrap();
+        while let Some(chunk) = finished.next().await {
+            chunk.unwrap();
+        }
+        drop(finished);
+        assert_eq!(warnings.load(Ordering::SeqCst), 0);
+
+        let mut abandoned = client.stream(&request).await.unwrap();
+        abandoned.next().await.unwrap().unwrap();
+        drop(abandoned);
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+    }
+}
+
+mod concurrency_limit {
+    use std::time::Duration;
+
+    use kind_openai::{
+        endpoints::{
+            chat::{ChatCompletion, Model},
+            StreamLimitPolicy,
+        },
+        user_message, OpenAI, OpenAIError,
+    };
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::common::TestAuth;
+
+    async fn client(policy: StreamLimitPolicy) -> (MockServer, OpenAI<TestAuth>) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string("data: [DONE]\n\n"),
+            )
+            .mount(&server)
+            .await;
+
+        let client = OpenAI::builder(TestAuth)
+            .base_url(server.uri())
+            .max_concurrent_streams(2)
+            .stream_limit_policy(policy)
+            .build()
+            .unwrap();
+        (server, client)
+    }
+
+    fn request() -> ChatCompletion<'static> {
+        ChatCompletion::model(Model::Gpt4oMini)
+            .messages(vec![user_message!("Hi!")])
+            .unstructured()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_waits_for_a_stream_to_be_dropped() {
+        let (_server, client) = client(StreamLimitPolicy::Wait).await;
+        let request = request();
+
+        let first = client.stream(&request).await.unwrap();
+        let _second = client.clone().stream(&request).await.unwrap();
+
+        let third = client.stream(&request);
+        tokio::pin!(third);
+        assert!(tokio::time::timeout(Duration::from_millis(100), &mut third)
+            .await
+            .is_err());
+
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), third)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn it_rejects_a_limit_of_zero_streams() {
+        let result = OpenAI::builder(TestAuth).max_concurrent_streams(0).build();
+
+        assert!(matches!(result, Err(OpenAIError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_streams_over_the_limit() {
+        let (_server, client) = client(StreamLimitPolicy::Reject).await;
+        let request = request();
+
+        let first = client.stream(&request).await.unwrap();
+        let _second = client.stream(&request).await.unwrap();
+
+        let err = client.stream(&request).await.err().unwrap();
+        assert!(matches!(err, OpenAIError::StreamLimitReached));
+        assert!(err.is_retryable());
+
+        drop(first);
+        client.stream(&request).await.unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "tokenizer")]
+
+use kind_openai::endpoints::chat::{logit_bias_for_words, Model};
+
+#[test]
+fn it_resolves_known_tokens() {
+    // "Hello" and " Hello" are each a single token in o200k_base, the tokenizer used by gpt-4o
+    assert_eq!(Model::Gpt4o.token_ids("Hello"), vec![13225]);
+    assert_eq!(Model::Gpt4o.token_ids(" Hello"), vec![32949]);
+}
+
+#[test]
+fn it_biases_words_with_and_without_a_leading_space() {
+    let logit_bias = logit_bias_for_words(Model::Gpt4o, &[("Hello", -100)]);
+
+    assert_eq!(
+        logit_bias,
+        kind_openai::logit_bias!(13225: -100, 32949: -100)
+    );
+}
+
+#[test]
+fn it_resolves_logit_biases_from_strings() {
+    assert_eq!(
+        kind_openai::logit_bias_str!("Hello" => -100),
+        kind_openai::logit_bias!(13225: -100, 32949: -100)
+    );
+    assert_eq!(
+        kind_openai::logit_bias_str!(Model::Gpt4oMini; "Hello" => 5),
+        kind_openai::logit_bias!(13225: 5, 32949: 5)
+    );
+}
+
+#[test]
+fn it_rejects_biases_out_of_range_instead_of_truncating_them() {
+    use kind_openai::{endpoints::chat::ChatCompletion, user_message, OpenAIError};
+
+    // 2^32 - 100 would wrap around to -100 if it were truncated to an `i32`
+    let bias: i64 = (1 << 32) - 100;
+    let logit_bias = kind_openai::logit_bias_str!("Hello" => bias);
+    assert!(logit_bias.values().all(|bias| *bias == i32::MAX));
+
+    let request = ChatCompletion::model(Model::Gpt4o)
+        .messages(vec![user_message!("Say hello.")])
+        .logit_bias(logit_bias)
+        .unstructured();
+    assert!(matches!(request, Err(OpenAIError::Validation(_))));
+
+    let logit_bias = kind_openai::logit_bias_str!("Hello" => -101);
+    let request = ChatCompletion::model(Model::Gpt4o)
+        .messages(vec![user_message!("Say hello.")])
+        .logit_bias(logit_bias)
+        .unstructured();
+    assert!(matches!(request, Err(OpenAIError::Validation(_))));
+}
+
+#[test]
+fn it_biases_every_token_of_multi_token_words() {
+    let word = String::from("Supercalifragilistic");
+    let tokens = Model::Gpt4o.token_ids(&word);
+    assert!(tokens.len() > 1);
+
+    let logit_bias = kind_openai::logit_bias_str!(word => -100);
+    for token in tokens {
+        assert_eq!(logit_bias.get(&token), Some(&-100));
+    }
+}
+
+#[test]
+fn it_counts_the_prompt_tokens_of_a_request() {
+    use kind_openai::{
+        endpoints::chat::ChatCompletion, system_message, user_message,
+        EnvironmentAuthTokenProvider, OpenAI,
+    };
+
+    let request = ChatCompletion::model(Model::Gpt4oMini)
+        .messages(vec![
+            system_message!("You are a helpful assistant."),
+            user_message!("Hello!"),
+        ])
+        .unstructured()
+        .unwrap();
+
+    // OpenAI reports 19 prompt tokens for exactly this request
+    let client = OpenAI::new(EnvironmentAuthTokenProvider);
+    assert_eq!(client.count_tokens(&request), 19);
+}
@@ -0,0 +1,6 @@
+#[derive(kind_openai::OpenAISchema)]
+struct MissingDeserialize {
+    name: String,
+}
+
+fn main() {}
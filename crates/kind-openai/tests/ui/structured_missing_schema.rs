@@ -0,0 +1,15 @@
+use kind_openai::{
+    endpoints::chat::{ChatCompletion, Model},
+    user_message,
+};
+
+#[derive(serde::Deserialize)]
+struct MissingSchema {
+    name: String,
+}
+
+fn main() {
+    let _ = ChatCompletion::model(Model::Gpt4oMini)
+        .messages(vec![user_message!("What's your name?")])
+        .structured::<MissingSchema>();
+}
@@ -0,0 +1,104 @@
+use kind_openai::Usage;
+
+#[test]
+fn it_computes_the_cache_hit_ratio() {
+    let usage: Usage = serde_json::from_str(
+        r#"{
+            "prompt_tokens": 2048,
+            "completion_tokens": 12,
+            "total_tokens": 2060,
+            "prompt_tokens_details": { "cached_tokens": 1536, "audio_tokens": 0 }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(usage.cached_tokens(), 1536);
+    assert_eq!(usage.cache_hit_ratio(), 0.75);
+}
+
+#[test]
+fn it_treats_missing_details_as_uncached() {
+    let usage: Usage = serde_json::from_str(
+        r#"{ "prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12 }"#,
+    )
+    .unwrap();
+
+    assert_eq!(usage.cached_tokens(), 0);
+    assert_eq!(usage.cache_hit_ratio(), 0.0);
+}
+
+#[test]
+fn it_does_not_divide_by_zero_prompt_tokens() {
+    let usage: Usage = serde_json::from_str(
+        r#"{
+            "prompt_tokens": 0,
+            "completion_tokens": 0,
+            "total_tokens": 0,
+            "prompt_tokens_details": { "cached_tokens": 0 }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(usage.cache_hit_ratio(), 0.0);
+}
+
+fn usage(prompt_tokens: u32, completion_tokens: u32, cached_tokens: Option<u32>) -> Usage {
+    let mut usage = serde_json::json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": prompt_tokens + completion_tokens,
+    });
+    if let Some(cached_tokens) = cached_tokens {
+        usage["prompt_tokens_details"] = serde_json::json!({ "cached_tokens": cached_tokens });
+    }
+    serde_json::from_value(usage).unwrap()
+}
+
+#[test]
+fn it_adds_usage_field_by_field() {
+    let mut total = usage(100, 10, Some(64)) + usage(50, 5, Some(32));
+    assert_eq!(total.prompt_tokens, 150);
+    assert_eq!(total.completion_tokens, 15);
+    assert_eq!(total.total_tokens, 165);
+    assert_eq!(total.cached_tokens(), 96);
+
+    total += usage(10, 1, None);
+    assert_eq!(total.total_tokens, 176);
+    assert_eq!(total.cached_tokens(), 96);
+}
+
+#[test]
+fn it_saturates_instead_of_overflowing() {
+    let total = usage(u32::MAX - 1, 0, Some(u32::MAX)) + usage(10, 1, Some(5));
+
+    assert_eq!(total.prompt_tokens, u32::MAX);
+    assert_eq!(total.completion_tokens, 1);
+    assert_eq!(total.total_tokens, u32::MAX);
+    assert_eq!(total.cached_tokens(), u32::MAX);
+}
+
+#[test]
+fn it_only_keeps_details_when_some_usage_reported_them() {
+    let without_details = usage(10, 1, None) + usage(20, 2, None);
+    assert!(without_details.prompt_tokens_details.is_none());
+
+    let with_details = usage(10, 1, None) + usage(20, 2, Some(0));
+    assert_eq!(with_details.prompt_tokens_details.unwrap().cached_tokens, 0);
+}
+
+#[test]
+fn it_sums_usage() {
+    let usages = [
+        usage(100, 10, Some(64)),
+        usage(50, 5, None),
+        usage(1, 1, Some(1)),
+    ];
+
+    let total = Usage::sum(usages);
+    assert_eq!(total.prompt_tokens, 151);
+    assert_eq!(total.total_tokens, 167);
+    assert_eq!(total.cached_tokens(), 65);
+
+    assert_eq!(usages.iter().sum::<Usage>(), total);
+    assert_eq!(Usage::sum([]), Usage::default());
+}
@@ -1,79 +1,88 @@
 use serde_json::{json, Value};
 use syn::{DataEnum, Expr, Fields, Lit};
 
-use crate::utils;
+use crate::{
+    rename::RenameRule,
+    utils::{self, Schema},
+};
 
 pub fn handle_enum(
     data: &DataEnum,
     has_repr: bool,
     description: Option<String>,
+    rename_all: Option<RenameRule>,
 ) -> Result<Value, syn::Error> {
-    let mut is_numeric_enum = true;
+    let mut has_discriminants = false;
     let mut variant_values = Vec::new();
+    // mirrors how rust assigns discriminants, where a variant without an explicit discriminant
+    // is one more than the variant before it (and the first variant starts at zero). `None` once that would
+    // overflow.
+    let mut next_discriminant = Some(0);
 
     for variant in &data.variants {
-        match &variant.fields {
-            Fields::Unit => {
-                if let Some((
-                    _,
-                    Expr::Lit(syn::ExprLit {
-                        lit: Lit::Int(lit_int),
-                        ..
-                    }),
-                )) = &variant.discriminant
-                {
-                    if !has_repr {
-                        return Err(syn::Error::new_spanned(
-                            lit_int,
-                            "repr attribute is required for enums with non-numeric variants.
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                "Only enums with unit variants are supported",
+            ));
+        }
+
+        let value = match &variant.discriminant {
+            Some((_, expr)) => {
+                if !has_repr {
+                    return Err(syn::Error::new_spanned(
+                        expr,
+                        "repr attribute is required for enums with non-numeric variants.
 NOTE: when using repr, ensure that you are using the `serde_repr` crate. It's impossible for us to detect that you are \
 actually using that deserializer, so you will get runtime deserialization errors if not as we always generate \
 numeric schemas when repr is detected.",
-                        ));
-                    }
-                    let int_value = lit_int
-                        .base10_parse::<i64>()
-                        .map_err(|e| syn::Error::new_spanned(lit_int, e))?;
-                    variant_values.push(json!(int_value));
-                } else {
-                    is_numeric_enum = false;
-                    break;
+                    ));
                 }
+                has_discriminants = true;
+                parse_discriminant(expr)?
             }
-            _ => {
-                return Err(syn::Error::new_spanned(
+            None => next_discriminant.ok_or_else(|| {
+                syn::Error::new_spanned(
                     &variant.ident,
-                    "Only enums with unit variants are supported",
-                ));
-            }
-        }
+                    "this variant's discriminant would be greater than `i64::MAX`, which isn't supported",
+                )
+            })?,
+        };
+
+        variant_values.push(json!(value));
+        next_discriminant = value.checked_add(1);
     }
 
-    let mut subordinate_schema = if is_numeric_enum && variant_values.len() == data.variants.len() {
+    let mut subordinate_schema = if has_discriminants {
         json!({
             "type": "number",
             "enum": variant_values
         })
     } else {
-        let variant_names = data
-            .variants
-            .iter()
-            .filter(|variant| !utils::get_serde_skip(&variant.attrs))
-            .map(|variant| match &variant.fields {
-                Fields::Unit => {
-                    if utils::get_description(&variant.attrs).is_some() {
-                        Err(syn::Error::new_spanned(
-                            &variant.ident,
-                            "Subordinate type descriptions should be located on the subordinate type itself and not on the field.",
-                        ))
-                    } else {
-                        Ok(utils::get_serde_rename(&variant.attrs)
-                            .unwrap_or_else(|| variant.ident.to_string()))
-                    }
-                },
-                _ => unreachable!(), // we've have already checked non-unit
-            })
-            .collect::<Result<Vec<String>, syn::Error>>()?;
+        let mut variant_names = Vec::new();
+        for variant in &data.variants {
+            let serde_attrs = utils::get_serde_attrs(&variant.attrs)?;
+            if serde_attrs.skip {
+                continue;
+            }
+
+            // we've already checked that every variant is a unit variant above
+            if utils::get_description(&variant.attrs).is_some() {
+                return Err(syn::Error::new_spanned(
+                    &variant.ident,
+                    "Subordinate type descriptions should be located on the subordinate type itself and not on the field.",
+                ));
+            }
+
+            // a variant's own rename always takes precedence over the enum's rename_all
+            variant_names.push(serde_attrs.rename.unwrap_or_else(|| {
+                let name = variant.ident.to_string();
+                match rename_all {
+                    Some(rule) => rule.apply_to_variant(&name),
+                    None => name,
+                }
+            }));
+        }
 
         json!({
             "type": "string",
@@ -87,3 +96,66 @@ numeric schemas when repr is detected.",
 
     Ok(subordinate_schema)
 }
+
+/// Generates the schema of an untagged enum, which is any one of its variants' inner schemas. Serde tries
+/// each variant in order and takes the first that deserializes, so only variants that wrap a single type
+/// are supported, since that type is all that the model gets to see.
+pub fn handle_untagged_enum(
+    data: &DataEnum,
+    description: Option<String>,
+) -> Result<Value, syn::Error> {
+    let mut variant_schemas = Vec::new();
+    for variant in &data.variants {
+        if utils::get_serde_attrs(&variant.attrs)?.skip {
+            continue;
+        }
+
+        let inner = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &variant.ident,
+                    "Only variants with a single unnamed field are supported in untagged enums",
+                ))
+            }
+        };
+
+        match utils::get_field_type(inner)? {
+            Schema::Inlined(schema) => variant_schemas.push(schema),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    inner,
+                    "Untagged enum variants can only wrap primitives, or Vecs and Options of them",
+                ))
+            }
+        }
+    }
+
+    let mut subordinate_schema = json!({ "anyOf": variant_schemas });
+    if let Some(description) = description {
+        subordinate_schema["description"] = Value::String(description);
+    }
+
+    Ok(subordinate_schema)
+}
+
+/// Parses an integer literal discriminant, which may be negative.
+fn parse_discriminant(expr: &Expr) -> Result<i64, syn::Error> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        }) => lit_int
+            .base10_parse::<i64>()
+            .map_err(|e| syn::Error::new_spanned(lit_int, e)),
+        Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => parse_discriminant(expr).map(|value| -value),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "Only integer literal discriminants are supported",
+        )),
+    }
+}
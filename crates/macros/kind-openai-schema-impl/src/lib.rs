@@ -1,14 +1,15 @@
 mod enum_gen;
+mod rename;
 mod struct_gen;
 mod utils;
 
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use struct_gen::GenSegment;
 use syn::{parse_macro_input, Data, DeriveInput};
 
 /// Places an associated function on a struct that returns an `&'static str` containing its OpenAI-compatible JSON schema.
-#[proc_macro_derive(OpenAISchema)]
+#[proc_macro_derive(OpenAISchema, attributes(openai_schema))]
 pub fn openai_schema_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -24,38 +25,93 @@ fn generate_openai_schema(input: &DeriveInput) -> Result<proc_macro2::TokenStrea
     // individual field docstrings are also extracted.
     let description = utils::get_description(&input.attrs);
     let repr = utils::has_repr_attr(&input.attrs)?;
+    let schema_attrs = utils::get_schema_attrs(&input.attrs)?;
 
-    if utils::has_top_level_serde_attr(&input.attrs) {
-        return Err(syn::Error::new_spanned(
-            &input.ident,
-            "Top-level serde attrs are not supported",
-        ));
-    }
+    let serde_attrs = utils::get_serde_container_attrs(&input.attrs)?;
 
     match &input.data {
         Data::Struct(data) => {
-            let tokens = struct_gen::handle_struct(data, name, description)?
-                .into_iter()
-                .map(|seg| match seg {
-                    GenSegment::Quote(subordinate_get_schema_method_call) => quote! {
-                        s.push_str(&#subordinate_get_schema_method_call);
-                    },
-                    GenSegment::StringLit(s) => quote! { s.push_str(&#s); },
+            if serde_attrs.untagged {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "untagged is only supported on enums",
+                ));
+            }
+
+            let segments = struct_gen::handle_struct(
+                data,
+                name,
+                description,
+                &schema_attrs,
+                serde_attrs.rename_all,
+            )?;
+
+            // the impl is bounded on this rather than leaning on the supertrait directly, so that forgetting
+            // `serde::Deserialize` fails the bound (with its readable message) instead of the supertrait, whose
+            // own error is easy to misread
+            let assert_deserialize = quote_spanned! {name.span()=>
+                where #name: ::kind_openai::__private::DerivesDeserialize
+            };
+
+            // adjacent literals are merged so that only subordinate schemas split the string up. without any
+            // subordinate schemas, the whole schema is known here and can be embedded as a single literal.
+            let mut merged = Vec::<GenSegment>::new();
+            for seg in segments {
+                match (merged.last_mut(), seg) {
+                    (Some(GenSegment::StringLit(prev)), GenSegment::StringLit(s)) => {
+                        prev.push_str(&s)
+                    }
+                    (_, seg) => merged.push(seg),
+                }
+            }
+
+            if let [GenSegment::StringLit(schema)] = merged.as_slice() {
+                return Ok(quote! {
+                    impl ::kind_openai::OpenAISchema for #name #assert_deserialize {
+                        fn openai_schema() -> ::kind_openai::GeneratedOpenAISchema {
+                            ::kind_openai::GeneratedOpenAISchema::from_static(#schema)
+                        }
+                    }
                 });
+            }
+
+            let tokens = merged.into_iter().map(|seg| match seg {
+                GenSegment::Quote(subordinate_get_schema_method_call) => quote! {
+                    s.push_str(&#subordinate_get_schema_method_call);
+                },
+                GenSegment::StringLit(s) => quote! { s.push_str(&#s); },
+            });
 
             Ok(quote! {
-                impl ::kind_openai::OpenAISchema for #name {
+                impl ::kind_openai::OpenAISchema for #name #assert_deserialize {
                     fn openai_schema() -> ::kind_openai::GeneratedOpenAISchema {
-                        use ::kind_openai::SubordinateOpenAISchema;
-                        let mut s = ::std::string::String::new();
-                        #(#tokens)*
-                        s.into()
+                        // built (and leaked) once per type, rather than on every call
+                        static SCHEMA: ::std::sync::OnceLock<::kind_openai::GeneratedOpenAISchema> =
+                            ::std::sync::OnceLock::new();
+                        *SCHEMA.get_or_init(|| {
+                            use ::kind_openai::SubordinateOpenAISchema;
+                            let mut s = ::std::string::String::new();
+                            #(#tokens)*
+                            s.into()
+                        })
                     }
                 }
             })
         }
         Data::Enum(data) => {
-            let schema = serde_json::to_string(&enum_gen::handle_enum(data, repr, description)?)
+            if schema_attrs.additional_properties {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "additional_properties is only supported on structs",
+                ));
+            }
+
+            let schema = if serde_attrs.untagged {
+                enum_gen::handle_untagged_enum(data, description)?
+            } else {
+                enum_gen::handle_enum(data, repr, description, serde_attrs.rename_all)?
+            };
+            let schema = serde_json::to_string(&schema)
                 .map_err(|err| syn::Error::new_spanned(&input.ident, err.to_string()))?;
 
             Ok(quote! {
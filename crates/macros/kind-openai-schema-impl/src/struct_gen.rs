@@ -8,7 +8,10 @@ use serde::Serialize;
 use serde_json::Value;
 use syn::{DataStruct, Fields};
 
-use crate::utils::Schema;
+use crate::{
+    rename::RenameRule,
+    utils::{Schema, SchemaAttrs},
+};
 
 pub enum GenSegment {
     StringLit(String),
@@ -81,52 +84,45 @@ pub fn handle_struct(
     data: &DataStruct,
     name: &Ident,
     description: Option<String>,
+    schema_attrs: &SchemaAttrs,
+    rename_all: Option<RenameRule>,
 ) -> Result<Vec<GenSegment>, syn::Error> {
     let mut segments = Vec::new();
 
+    let fields = collect_field_infos(&data.fields, rename_all)?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // openai's strict mode rejects any schema where a property is missing from `required`, so as soon as
+    // a field is allowed to be omitted we have to fall back to a non-strict schema. the same goes for
+    // allowing additional properties, which strict mode doesn't support either.
+    let strict = !schema_attrs.additional_properties && fields.iter().all(|field| field.required);
+
     // the root of the schema that contains a non-delimited object that contains the properties
     segments.push(GenSegment::StringLit(format!(
-        r#"{{"name":{},"description":{},"strict":true,"schema":{{"type":"object","additionalProperties":false,"properties":{{"#,
+        r#"{{"name":{},"description":{},"strict":{},"schema":{{"type":"object","additionalProperties":{},"properties":{{"#,
         JsonField(&name.to_string()),
-        JsonField(&description)
+        JsonField(&description),
+        strict,
+        schema_attrs.additional_properties,
     )));
 
     let mut required_fields = Vec::new();
 
-    for field in collect_field_infos(&data.fields)?.into_iter().flatten() {
+    for field in fields {
         segments.push(GenSegment::StringLit(format!(
             "{}:",
             // serialize the field name as a string since it will hopefully be a valid json key
             JsonField(&field.name)
         )));
 
-        match field.schema {
-            Schema::Inlined(mut schema) => {
-                // modify the provided schema to contain the description since the codepoint where the schema object is made
-                // does not have access the any kind of description data
-                if let (Some(description), Some(obj)) =
-                    (field.description.as_ref(), schema.as_object_mut())
-                {
-                    obj.insert(
-                        "description".to_string(),
-                        Value::String(description.clone()),
-                    );
-                }
-
-                segments.push(GenSegment::StringLit(JsonField(&schema).to_string()));
-            }
-            Schema::Subordinate(ty_name) => {
-                if field.description.is_some() {
-                    return Err(syn::Error::new_spanned(
-                        &field.name,
-                        "Subordinate type descriptions should be located on the subordinate type itself and not on the field.",
-                    ));
-                }
-                segments.push(GenSegment::Quote(quote! {
-                    #ty_name::subordinate_openai_schema()
-                }))
-            }
-        }
+        push_schema(
+            &mut segments,
+            field.schema,
+            field.description.as_ref(),
+            &field.name,
+        )?;
 
         if field.required {
             required_fields.push(field.name);
@@ -149,13 +145,99 @@ pub fn handle_struct(
     Ok(segments)
 }
 
-fn collect_field_infos(fields: &Fields) -> Result<Vec<Option<field::FieldInfo>>, syn::Error> {
+fn push_schema(
+    segments: &mut Vec<GenSegment>,
+    schema: Schema,
+    description: Option<&String>,
+    field_name: &str,
+) -> Result<(), syn::Error> {
+    match schema {
+        Schema::Inlined(mut schema) => {
+            // modify the provided schema to contain the description since the codepoint where the schema object is made
+            // does not have access the any kind of description data
+            if let (Some(description), Some(obj)) = (description, schema.as_object_mut()) {
+                obj.insert(
+                    "description".to_string(),
+                    Value::String(description.clone()),
+                );
+            }
+
+            segments.push(GenSegment::StringLit(JsonField(&schema).to_string()));
+        }
+        Schema::Subordinate(ty_name) => {
+            if description.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    "Subordinate type descriptions should be located on the subordinate type itself and not on the field.",
+                ));
+            }
+            segments.push(GenSegment::Quote(quote! {
+                #ty_name::subordinate_openai_schema()
+            }))
+        }
+        Schema::Array { items, len } => {
+            push_array(segments, *items, len, false, description, field_name)?
+        }
+        Schema::Nullable(schema) => match *schema {
+            // arrays are made nullable the same way as inlined schemas, by allowing null as a type
+            Schema::Array { items, len } => {
+                push_array(segments, *items, len, true, description, field_name)?
+            }
+            // subordinate schemas may not have a single type, so they're wrapped instead
+            schema => {
+                segments.push(GenSegment::StringLit(r#"{"anyOf":["#.to_string()));
+                push_schema(segments, schema, description, field_name)?;
+                segments.push(GenSegment::StringLit(r#",{"type":"null"}]}"#.to_string()));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn push_array(
+    segments: &mut Vec<GenSegment>,
+    items: Schema,
+    len: Option<usize>,
+    nullable: bool,
+    description: Option<&String>,
+    field_name: &str,
+) -> Result<(), syn::Error> {
+    // open the array object, leaving the items to be filled in by the (possibly subordinate) item schema
+    let description = description
+        .map(|description| format!(r#""description":{},"#, JsonField(description)))
+        .unwrap_or_default();
+    let ty = if nullable {
+        r#"["array","null"]"#
+    } else {
+        r#""array""#
+    };
+    let len = len
+        .map(|len| format!(r#""minItems":{len},"maxItems":{len},"#))
+        .unwrap_or_default();
+    segments.push(GenSegment::StringLit(format!(
+        r#"{{{description}{len}"type":{ty},"items":"#
+    )));
+    // descriptions belong to the array as a whole rather than to its items
+    push_schema(segments, items, None, field_name)?;
+    segments.push(GenSegment::StringLit("}".to_string()));
+
+    Ok(())
+}
+
+// the struct's `rename_all` only applies to its own named fields. nested types derive their schemas
+// separately, so their own renaming rules (or lack thereof) are never affected by it.
+fn collect_field_infos(
+    fields: &Fields,
+    rename_all: Option<RenameRule>,
+) -> Result<Vec<Option<field::FieldInfo>>, syn::Error> {
     match fields {
         Fields::Named(fields_named) => fields_named
             .named
             .iter()
             .enumerate()
             .map(Into::<field::FieldWithGuaranteedName>::into)
+            .map(|field| field.with_rename_all(rename_all))
             .map(TryInto::try_into)
             .collect(),
         Fields::Unnamed(fields_unnamed) => fields_unnamed
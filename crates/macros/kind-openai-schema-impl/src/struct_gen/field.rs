@@ -1,6 +1,6 @@
 use syn::{Field, Ident};
 
-use crate::utils;
+use crate::{rename::RenameRule, utils};
 
 /// Our interpretable representation of a struct field with all necessary metadata
 /// to turn it into a JSON schema object.
@@ -16,27 +16,60 @@ impl<'a> TryFrom<FieldWithGuaranteedName<'a>> for Option<FieldInfo> {
 
     fn try_from(value: FieldWithGuaranteedName<'a>) -> Result<Self, Self::Error> {
         let attrs = value.attrs();
-        if utils::get_serde_skip(attrs) {
+        let serde_attrs = utils::get_serde_attrs(attrs)?;
+        if serde_attrs.skip {
             return Ok(None);
         }
 
-        let field_schema = utils::get_field_type(value.ty())?;
-        let description = utils::get_description(attrs);
-        let name = utils::get_serde_rename(attrs).unwrap_or_else(|| value.name());
+        let field_schema_attrs = utils::get_field_schema_attrs(attrs)?;
+        let field_schema = match field_schema_attrs.as_type {
+            Some(as_type) => utils::get_overridden_field_type(value.ty(), &as_type),
+            None => utils::get_field_type(value.ty())?,
+        };
+        let default = match field_schema_attrs.default {
+            // the note is folded into the field's description, which subordinate types can't have
+            Some(default) if is_subordinate(&field_schema) => {
+                return Err(syn::Error::new_spanned(
+                    default,
+                    "`default` can't be used on fields of a type that derives `OpenAISchema`, since the note is \
+                     added to the field's description. Mention the default in the type's own docstring instead.",
+                ));
+            }
+            default => default.map(|default| default.value()),
+        };
+        let description = match (utils::get_description(attrs), default) {
+            (Some(description), Some(default)) => {
+                Some(format!("{description} (default: {default})"))
+            }
+            (None, Some(default)) => Some(format!("(default: {default})")),
+            (description, None) => description,
+        };
+        // a field's own rename always takes precedence over the struct's rename_all
+        let name = serde_attrs.rename.unwrap_or_else(|| value.name());
 
         Ok(Some(FieldInfo {
             name,
             schema: field_schema,
-            // required currently must always be true, so we don't even bother including it in `get_field_type` for now.
-            required: true,
+            // serde will fill in defaulted fields when they're missing, so the model is free to omit them.
+            required: !serde_attrs.default,
             description,
         }))
     }
 }
 
+// whether the field's schema is a subordinate type's, which (unlike arrays of them) can't carry a description
+fn is_subordinate(schema: &utils::Schema) -> bool {
+    match schema {
+        utils::Schema::Subordinate(_) => true,
+        utils::Schema::Nullable(schema) => is_subordinate(schema),
+        utils::Schema::Inlined(_) | utils::Schema::Array { .. } => false,
+    }
+}
+
 pub struct FieldWithGuaranteedName<'a> {
     backing_field: &'a Field,
     name: FieldName,
+    rename_all: Option<RenameRule>,
 }
 
 enum FieldName {
@@ -76,13 +109,21 @@ impl<'a> From<(usize, &'a Field)> for FieldWithGuaranteedName<'a> {
         Self {
             backing_field: field,
             name,
+            rename_all: None,
         }
     }
 }
 
 impl<'a> FieldWithGuaranteedName<'a> {
+    pub fn with_rename_all(self, rename_all: Option<RenameRule>) -> Self {
+        Self { rename_all, ..self }
+    }
+
     fn name(&self) -> String {
-        self.name.to_string()
+        match (&self.name, self.rename_all) {
+            (FieldName::Named(name), Some(rule)) => rule.apply_to_field(name),
+            (name, _) => name.to_string(),
+        }
     }
 
     fn ty(&self) -> &syn::Type {
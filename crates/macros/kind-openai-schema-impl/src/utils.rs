@@ -1,6 +1,8 @@
 use serde_json::{json, Value};
 use syn::{Attribute, Ident, Type};
 
+use crate::rename::RenameRule;
+
 /// Extracts the description to provide to the JSON schema by scraping and reading triple-slash doc comments.
 /// This works on top-level structs, top-level enums, and individual struct fields. AFAIK it's not possible to
 /// place descriptions on enum variants according to JSON schema (nor that it would even be useful to do so),
@@ -34,10 +36,68 @@ pub fn get_description(attrs: &[Attribute]) -> Option<String> {
     }
 }
 
-pub fn has_top_level_serde_attr(attrs: &[Attribute]) -> bool {
-    attrs
-        .iter()
-        .any(|attr| matches!(get_serde_meta_item(attr), Ok(Some(_))))
+/// The serde attributes on the top level of a type that affect its schema.
+#[derive(Default)]
+pub struct SerdeContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub untagged: bool,
+}
+
+/// Parses the top-level `#[serde(...)]` attributes, rejecting any that the schema can't account for.
+pub fn get_serde_container_attrs(attrs: &[Attribute]) -> Result<SerdeContainerAttrs, syn::Error> {
+    let mut container_attrs = SerdeContainerAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("serde") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("untagged") {
+                container_attrs.untagged = true;
+                return Ok(());
+            }
+
+            // this only renames the fields of struct variants, which enums with a schema never have. the
+            // rule still has to be consumed so that the parser can move on to the next item.
+            if meta.path.is_ident("rename_all_fields") {
+                if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::LitStr>()?;
+                } else {
+                    meta.parse_nested_meta(|rename_all_fields| {
+                        rename_all_fields.value()?.parse::<syn::LitStr>()?;
+                        Ok(())
+                    })?;
+                }
+                return Ok(());
+            }
+
+            if !meta.path.is_ident("rename_all") {
+                return Err(meta.error(
+                    "Top-level serde attrs other than rename_all, rename_all_fields, and untagged are not supported",
+                ));
+            }
+
+            if meta.input.peek(syn::Token![=]) {
+                container_attrs.rename_all = Some(RenameRule::from_lit(
+                    &meta.value()?.parse::<syn::LitStr>()?,
+                )?);
+            } else {
+                // `rename_all(serialize = "...", deserialize = "...")`, where the deserialize rule is the
+                // one that the model needs to follow.
+                meta.parse_nested_meta(|rename_all| {
+                    let lit = rename_all.value()?.parse::<syn::LitStr>()?;
+                    if rename_all.path.is_ident("deserialize") {
+                        container_attrs.rename_all = Some(RenameRule::from_lit(&lit)?);
+                    }
+                    Ok(())
+                })?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(container_attrs)
 }
 
 pub fn has_repr_attr(attrs: &[Attribute]) -> Result<bool, syn::Error> {
@@ -71,41 +131,151 @@ pub fn has_repr_attr(attrs: &[Attribute]) -> Result<bool, syn::Error> {
     Ok(repr.is_some())
 }
 
-pub fn get_serde_rename(attrs: &[Attribute]) -> Option<String> {
-    attrs
-        .iter()
-        .find_map(|attr| match get_serde_meta_item(attr) {
-            Ok(Some(tokens)) => {
-                let tokens = tokens.to_string();
-                match tokens.find("rename =") {
-                    Some(rename_start) => {
-                        let quote_start = tokens[rename_start..].find('"')?;
-                        let start = rename_start + quote_start + 1;
-                        let end = tokens[start..].find('"')?;
-                        Some(tokens[start..start + end].to_string())
-                    }
-                    None => None,
+/// The serde attributes on a field or enum variant that affect its schema.
+#[derive(Default)]
+pub struct SerdeAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub default: bool,
+}
+
+/// Parses every `#[serde(...)]` attribute on a field or enum variant structurally, so that attributes like
+/// `alias` or `skip_serializing_if` can't be mistaken for the ones that we care about.
+pub fn get_serde_attrs(attrs: &[Attribute]) -> Result<SerdeAttrs, syn::Error> {
+    let mut serde_attrs = SerdeAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("serde") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                if meta.input.peek(syn::Token![=]) {
+                    serde_attrs.rename = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else {
+                    // `rename(serialize = "...", deserialize = "...")`, where the deserialize name is the
+                    // one that the model needs to produce.
+                    meta.parse_nested_meta(|rename| {
+                        let value = rename.value()?.parse::<syn::LitStr>()?.value();
+                        if rename.path.is_ident("deserialize") {
+                            serde_attrs.rename = Some(value);
+                        }
+                        Ok(())
+                    })?;
                 }
+                return Ok(());
             }
-            _ => None,
-        })
+
+            if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                serde_attrs.skip = true;
+            } else if meta.path.is_ident("default") {
+                serde_attrs.default = true;
+            }
+
+            // consume the value of any other `key = value` or `key(...)` pair so that the parser can move
+            // on to the next item.
+            if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<proc_macro2::TokenStream>()?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(serde_attrs)
 }
 
-pub fn get_serde_skip(attrs: &[Attribute]) -> bool {
-    attrs.iter().any(|attr| match get_serde_meta_item(attr) {
-        Ok(Some(tokens)) => tokens.to_string().contains("skip"),
-        _ => false,
-    })
+/// The options that can be provided with `#[openai_schema(...)]` on the top level of a type.
+#[derive(Default)]
+pub struct SchemaAttrs {
+    /// Allows the model to include properties beyond the ones that are declared.
+    pub additional_properties: bool,
 }
 
-fn get_serde_meta_item(attr: &Attribute) -> syn::Result<Option<&proc_macro2::TokenStream>> {
-    if attr.path().is_ident("serde") {
-        match &attr.meta {
-            syn::Meta::List(meta) => Ok(Some(&meta.tokens)),
-            bad => Err(syn::Error::new_spanned(bad, "unrecognized attribute")),
+pub fn get_schema_attrs(attrs: &[Attribute]) -> Result<SchemaAttrs, syn::Error> {
+    let mut schema_attrs = SchemaAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("openai_schema") {
+            continue;
         }
-    } else {
-        Ok(None)
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("additional_properties") {
+                schema_attrs.additional_properties = true;
+                Ok(())
+            } else {
+                Err(meta.error("unrecognized openai_schema attribute"))
+            }
+        })?;
+    }
+
+    Ok(schema_attrs)
+}
+
+/// The options that can be provided with `#[openai_schema(...)]` on a struct field.
+#[derive(Default)]
+pub struct FieldSchemaAttrs {
+    /// The JSON type that the field is serialized as, for fields whose wire type can't be inferred
+    /// from their Rust type (such as those using `serde(with)`).
+    pub as_type: Option<String>,
+    /// The value the model should fall back to when unsure, which is noted in the field's description
+    /// since strict schemas don't support the `default` keyword.
+    pub default: Option<syn::LitStr>,
+}
+
+pub fn get_field_schema_attrs(attrs: &[Attribute]) -> Result<FieldSchemaAttrs, syn::Error> {
+    let mut field_schema_attrs = FieldSchemaAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("openai_schema") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("as") {
+                let lit = meta.value()?.parse::<syn::LitStr>()?;
+                let as_type = lit.value();
+                if !matches!(
+                    as_type.as_str(),
+                    "string" | "integer" | "number" | "boolean"
+                ) {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "expected one of \"string\", \"integer\", \"number\", or \"boolean\"",
+                    ));
+                }
+                field_schema_attrs.as_type = Some(as_type);
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                field_schema_attrs.default = Some(meta.value()?.parse::<syn::LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unrecognized openai_schema field attribute"))
+            }
+        })?;
+    }
+
+    Ok(field_schema_attrs)
+}
+
+/// The schema of a field whose wire type was provided explicitly. `Option`s are still nullable, since
+/// `serde(with)` modules are usually written for the inner type.
+pub fn get_overridden_field_type(ty: &Type, as_type: &str) -> Schema {
+    let schema = Schema::Inlined(json!({ "type": as_type }));
+    match ty {
+        Type::Path(type_path)
+            if type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Option") =>
+        {
+            merge_with_null(schema)
+        }
+        _ => schema,
     }
 }
 
@@ -113,6 +283,14 @@ fn get_serde_meta_item(attr: &Attribute) -> syn::Result<Option<&proc_macro2::Tok
 pub enum Schema {
     Subordinate(Ident),
     Inlined(Value),
+    /// An array whose items can't be inlined because they (eventually) contain a subordinate type. Fixed-size
+    /// arrays also carry their length.
+    Array {
+        items: Box<Schema>,
+        len: Option<usize>,
+    },
+    /// A schema that can't be inlined, which also accepts null.
+    Nullable(Box<Schema>),
 }
 
 /// This is the core util that underlies most of this crate, effectively this takes in a Rust type
@@ -135,15 +313,17 @@ pub fn get_field_type(ty: &Type) -> Result<Schema, syn::Error> {
                 "Vec" => {
                     if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                         if let Some(syn::GenericArgument::Type(inner_type)) = args.args.first() {
-                            let inner_schema = get_field_type(inner_type)?;
-                            let items = match inner_schema {
-                                Schema::Subordinate(_name) => todo!(),
-                                Schema::Inlined(schema) => schema,
-                            };
-                            Ok(Schema::Inlined(json!({
-                                "type": "array",
-                                "items": items,
-                            })))
+                            match get_field_type(inner_type)? {
+                                Schema::Inlined(items) => Ok(Schema::Inlined(json!({
+                                    "type": "array",
+                                    "items": items,
+                                }))),
+                                // the items are only known once the subordinate type's schema is looked up
+                                items => Ok(Schema::Array {
+                                    items: Box::new(items),
+                                    len: None,
+                                }),
+                            }
                         } else {
                             Err(syn::Error::new_spanned(
                                 args,
@@ -161,8 +341,7 @@ pub fn get_field_type(ty: &Type) -> Result<Schema, syn::Error> {
                     if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                         if let Some(syn::GenericArgument::Type(inner_type)) = args.args.first() {
                             let inner_schema = get_field_type(inner_type)?;
-                            let schema_with_null = merge_with_null(inner_schema);
-                            Ok(Schema::Inlined(schema_with_null))
+                            Ok(merge_with_null(inner_schema))
                         } else {
                             Err(syn::Error::new_spanned(
                                 args,
@@ -179,12 +358,39 @@ pub fn get_field_type(ty: &Type) -> Result<Schema, syn::Error> {
                 _hopefully_an_enum => Ok(Schema::Subordinate(segment.ident.clone())),
             }
         }
+        Type::Array(type_array) => {
+            let len = match &type_array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(lit_int),
+                    ..
+                }) => lit_int.base10_parse::<usize>()?,
+                len => {
+                    return Err(syn::Error::new_spanned(
+                        len,
+                        "Only integer literal array lengths are supported",
+                    ))
+                }
+            };
+
+            match get_field_type(&type_array.elem)? {
+                Schema::Inlined(items) => Ok(Schema::Inlined(json!({
+                    "type": "array",
+                    "items": items,
+                    "minItems": len,
+                    "maxItems": len,
+                }))),
+                items => Ok(Schema::Array {
+                    items: Box::new(items),
+                    len: Some(len),
+                }),
+            }
+        }
         _ => Err(syn::Error::new_spanned(ty, "Unsupported type")),
     }
 }
 
-fn merge_with_null(schema: Schema) -> Value {
-    match schema {
+fn merge_with_null(schema: Schema) -> Schema {
+    let schema = match schema {
         Schema::Inlined(ref schema @ Value::Object(ref map)) => {
             if let Some(Value::String(type_str)) = map.get("type") {
                 let mut map = map.clone();
@@ -211,6 +417,13 @@ fn merge_with_null(schema: Schema) -> Value {
                 { "type": "null" }
             ]
         }),
-        Schema::Subordinate(_) => todo!(),
-    }
+        // the schema is only known at runtime, so making it nullable is left to the generated code
+        Schema::Subordinate(_) | Schema::Array { .. } => {
+            return Schema::Nullable(Box::new(schema));
+        }
+        // `Option<Option<T>>` deserializes both nulls into the outer `None`, so it's no more nullable
+        Schema::Nullable(_) => return schema,
+    };
+
+    Schema::Inlined(schema)
 }
@@ -0,0 +1,7 @@
+// checks the errors the derive reports for types it can't generate a schema for, since the point of them is to
+// be readable rather than just to fail the build.
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
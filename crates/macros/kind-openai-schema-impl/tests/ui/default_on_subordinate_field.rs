@@ -0,0 +1,13 @@
+#[derive(serde::Deserialize, kind_openai::OpenAISchema)]
+enum Tone {
+    Formal,
+    Casual,
+}
+
+#[derive(serde::Deserialize, kind_openai::OpenAISchema)]
+struct Reply {
+    #[openai_schema(default = "Casual")]
+    tone: Option<Tone>,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[derive(serde_repr::Deserialize_repr, kind_openai::OpenAISchema)]
+#[repr(u64)]
+enum Huge {
+    Last = 9223372036854775807,
+    Overflowing,
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+//! Deserializers that are more forgiving of the mistakes models make in structured outputs.
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::{Number, Value};
+
+/// Deserializes a `serde_repr` enum from either its number or the number as a string (`5` or `"5"`),
+/// since models occasionally quote numbers even when the schema asks for an integer. The enum's schema
+/// is unaffected.
+///
+/// ```rust,ignore
+/// #[derive(Deserialize, OpenAISchema)]
+/// struct NicenessScoreContainer {
+///     #[serde(deserialize_with = "kind_openai::de::lenient_repr")]
+///     niceness_score: NicenessScore,
+/// }
+/// ```
+pub fn lenient_repr<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(Number),
+        String(String),
+    }
+
+    let number = match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(number) => number,
+        NumberOrString::String(string) => string.trim().parse().map_err(|_| {
+            serde::de::Error::custom(format!(
+                "expected a number or numeric string, got {string:?}"
+            ))
+        })?,
+    };
+
+    T::deserialize(Value::Number(number)).map_err(serde::de::Error::custom)
+}
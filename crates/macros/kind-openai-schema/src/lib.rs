@@ -1,6 +1,8 @@
 //! A procedural macro for deriving an OpenAI-compatible JSON schema for a Rust
 //! struct.
 
+pub mod de;
+
 use std::fmt::Display;
 
 pub use kind_openai_schema_impl::OpenAISchema;
@@ -24,6 +26,56 @@ impl From<String> for GeneratedOpenAISchema {
     }
 }
 
+impl GeneratedOpenAISchema {
+    /// Wraps a schema that's known at compile time, which the derive uses for structs without any
+    /// subordinate types so that nothing has to be built or leaked at runtime.
+    pub const fn from_static(schema: &'static str) -> Self {
+        Self(schema)
+    }
+
+    /// The schema as a JSON string.
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    /// Parses the schema into a JSON value.
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::from_str(self.0).expect("generated schemas are always valid JSON")
+    }
+
+    /// Gives an owned copy of the schema with its name replaced, and its description too if one is provided.
+    /// The name must only contain letters, digits, underscores, and dashes.
+    ///
+    /// The copy is a plain JSON value rather than another `GeneratedOpenAISchema`, so that schemas which are
+    /// patched per request are freed along with the request instead of living for the rest of the program.
+    pub fn with_name_and_description(
+        &self,
+        name: &str,
+        description: Option<&str>,
+    ) -> serde_json::Value {
+        let mut schema = self.to_value();
+        schema["name"] = name.into();
+        if let Some(description) = description {
+            schema["description"] = description.into();
+        }
+        schema
+    }
+
+    /// Gives an owned copy of the schema with its description replaced, keeping the name.
+    pub fn with_description(&self, description: &str) -> serde_json::Value {
+        let mut schema = self.to_value();
+        schema["description"] = description.into();
+        schema
+    }
+
+    /// Whether or not the schema is semantically equal to the provided JSON, ignoring whitespace and key order.
+    pub fn matches(&self, expected: &str) -> bool {
+        serde_json::from_str::<serde_json::Value>(expected)
+            .map(|expected| self.to_value() == expected)
+            .unwrap_or(false)
+    }
+}
+
 impl Serialize for GeneratedOpenAISchema {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -39,7 +91,29 @@ impl Serialize for GeneratedOpenAISchema {
 /// Docstrings on the top level of a type will automatically be consumed and provided to the schema,
 /// as well as any docstrings on fields of said struct.
 ///
-/// Additionally, `serde(skip)` and `serde(rename)` on fields works perfectly fine.
+/// Additionally, `serde(skip)` and `serde(rename)` on fields works perfectly fine. Fields annotated with
+/// `serde(default)` are left out of the `required` array so the model is allowed to omit them. Note that
+/// OpenAI's strict mode requires every field to be required, so a schema containing defaulted fields is
+/// generated with `"strict": false`.
+///
+/// Structs and enums may also use `serde(rename_all)`, which is applied to every field or variant that isn't
+/// renamed individually. The rule only applies to the type it's placed on, so a nested enum keeps its own
+/// casing regardless of the struct containing it.
+/// Enums annotated with `serde(untagged)` may have variants that each wrap a single primitive (or a `Vec` or
+/// `Option` of one), such as `enum Value { Number(f64), Text(String) }`, which produces an `anyOf` of the
+/// wrapped types.
+///
+/// Structs can be annotated with `#[openai_schema(additional_properties)]` to allow the model to include
+/// properties beyond the declared ones, which also makes the schema non-strict. Serde ignores unknown
+/// fields by default, so those extra properties are dropped unless they're captured with `serde(flatten)`.
+///
+/// Fields whose wire type doesn't match their Rust type, such as those using `serde(with)`, can declare it
+/// with `#[openai_schema(as = "integer")]` (or `"string"`, `"number"`, or `"boolean"`).
+///
+/// Strict schemas don't support the `default` keyword, so a field can instead hint at the value the model
+/// should fall back to with `#[openai_schema(default = "unknown")]`, which appends `(default: unknown)` to
+/// its description. This only affects the schema, so pair it with `serde(default)` if the field may be omitted.
+/// Fields whose type derives `OpenAISchema` itself can't have a description, so the hint is rejected on them.
 ///
 /// For example:
 /// ```rust,ignore
@@ -100,14 +174,22 @@ impl Serialize for GeneratedOpenAISchema {
 ///   enums must be used as a field in a containing struct.
 /// - Enums must be unit variants. Enums with int descriminants (for example `enum MyEnum { Variant1 = 1, Variant2 = 2 }`) are also
 ///   allowed, but they must be annotated with `repr(i32)` or similar, and derive `Deserialize_repr` from `serde_repr`.
+///   Variants without an explicit discriminant are numbered the same way Rust numbers them.
+///   Models sometimes send these numbers as strings, which `de::lenient_repr` accepts too.
 /// - Struct fields are allowed to be any of the following types:
 ///     - `String`
 ///     - All int types, (`i32`, `i64`, `u32`, `u64`, `isize`, `usize`, etc.)
 ///     - `f32` and `f64`
 ///     - `bool`
 ///     - Any unit enum type which also derives `OpenAISchema`
-///     - `Vec<T>` where `T` is any of the above types
+///     - `Vec<T>` where `T` is any of the above types, including another `Vec`
+///     - Fixed-size arrays `[T; N]` where `T` is any of the above types, which are limited to exactly `N` items
 ///     - `Option<T>` where `T` is any of the above types
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be used as a structured output schema",
+    label = "`{Self}` doesn't implement `OpenAISchema`",
+    note = "your type must derive both `kind_openai::OpenAISchema` and `serde::Deserialize`"
+)]
 pub trait OpenAISchema: for<'de> Deserialize<'de> {
     fn openai_schema() -> GeneratedOpenAISchema;
 }
@@ -119,3 +201,95 @@ pub trait SubordinateOpenAISchema {
     /// Partial schema that will be filled in in the top level schema.
     fn subordinate_openai_schema() -> &'static str;
 }
+
+/// Asserts that the schema generated for a type is semantically equal to the expected JSON, ignoring
+/// whitespace and key order. On a mismatch, every path at which the schemas differ is reported.
+///
+/// ```rust,ignore
+/// assert_schema_eq!(
+///     MySchema,
+///     r#"{ "name": "MySchema", "description": null, "strict": true, "schema": { ... } }"#
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_schema_eq {
+    ($ty:ty, $expected:expr $(,)?) => {
+        $crate::__private::assert_schema_eq(
+            <$ty as $crate::OpenAISchema>::openai_schema(),
+            $expected,
+        )
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use crate::GeneratedOpenAISchema;
+
+    /// Checked by the derive, so that forgetting `serde::Deserialize` points at both derives rather than
+    /// only at the missing trait bound.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` derives `OpenAISchema` but not `serde::Deserialize`",
+        label = "`{Self}` doesn't implement `serde::Deserialize`",
+        note = "your type must derive both `kind_openai::OpenAISchema` and `serde::Deserialize`"
+    )]
+    pub trait DerivesDeserialize: for<'de> Deserialize<'de> {}
+
+    impl<T> DerivesDeserialize for T where T: for<'de> Deserialize<'de> {}
+
+    #[track_caller]
+    pub fn assert_schema_eq(actual: GeneratedOpenAISchema, expected: &str) {
+        let expected = serde_json::from_str::<Value>(expected)
+            .unwrap_or_else(|err| panic!("expected schema is not valid JSON: {err}"));
+        let actual = actual.to_value();
+
+        let mut differences = Vec::new();
+        diff(&actual, &expected, "$".to_owned(), &mut differences);
+
+        if !differences.is_empty() {
+            panic!(
+                "generated schema does not match the expected schema:\n{}\n\ngenerated schema:\n{}",
+                differences.join("\n"),
+                serde_json::to_string_pretty(&actual).unwrap()
+            );
+        }
+    }
+
+    fn diff(actual: &Value, expected: &Value, path: String, differences: &mut Vec<String>) {
+        match (actual, expected) {
+            (Value::Object(actual), Value::Object(expected)) => {
+                for (key, expected_value) in expected {
+                    match actual.get(key) {
+                        Some(actual_value) => diff(
+                            actual_value,
+                            expected_value,
+                            format!("{path}.{key}"),
+                            differences,
+                        ),
+                        None => differences.push(format!(
+                            "  {path}.{key}: missing, expected {expected_value}"
+                        )),
+                    }
+                }
+                for (key, actual_value) in actual {
+                    if !expected.contains_key(key) {
+                        differences.push(format!("  {path}.{key}: unexpected {actual_value}"));
+                    }
+                }
+            }
+            (Value::Array(actual_items), Value::Array(expected_items))
+                if actual_items.len() == expected_items.len() =>
+            {
+                for (i, (actual, expected)) in actual_items.iter().zip(expected_items).enumerate() {
+                    diff(actual, expected, format!("{path}[{i}]"), differences);
+                }
+            }
+            (actual, expected) if actual != expected => {
+                differences.push(format!("  {path}: expected {expected}, got {actual}"))
+            }
+            _ => {}
+        }
+    }
+}